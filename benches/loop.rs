@@ -9,16 +9,8 @@ fn run_loop_contract() {
 	let config = Config::istanbul();
 
 	let vicinity = MemoryVicinity {
-		gas_price: U256::zero(),
-		origin: H160::default(),
-		block_hashes: Vec::new(),
-		block_number: Default::default(),
-		block_coinbase: Default::default(),
-		block_timestamp: Default::default(),
-		block_difficulty: Default::default(),
-		block_gas_limit: Default::default(),
 		chain_id: U256::one(),
-		block_base_fee_per_gas: U256::zero(),
+		..Default::default()
 	};
 
 	let mut state = BTreeMap::new();
@@ -53,6 +53,50 @@ impl ExitReason {
 	pub fn is_fatal(&self) -> bool {
 		matches!(self, Self::Fatal(_))
 	}
+
+	/// Stable numeric status code of the exit reason, for hosts that need to
+	/// pass it across an ABI boundary. The family (succeed, error, revert,
+	/// fatal) is in bits 24..32, the variant in bits 16..24 and any payload
+	/// in the low 16 bits. Messages of `Other` variants are not encoded.
+	pub fn to_u64(&self) -> u64 {
+		match self {
+			Self::Succeed(s) => (*s as u64) << 16,
+			Self::Error(e) => 1 << 24 | u64::from(e.to_code()),
+			Self::Revert(r) => 2 << 24 | (*r as u64) << 16,
+			Self::Fatal(f) => {
+				3 << 24
+					| match f {
+						ExitFatal::NotSupported => 0,
+						ExitFatal::UnhandledInterrupt => 1 << 16,
+						ExitFatal::CallErrorAsFatal(e) => 2 << 16 | u64::from(e.to_code()),
+						ExitFatal::Other(_) => 3 << 16,
+					}
+			}
+		}
+	}
+
+	/// Decode a status code produced by `to_u64`. `Other` variants decode
+	/// with an empty message. Returns `None` for unknown codes.
+	pub fn from_u64(code: u64) -> Option<Self> {
+		let variant = (code >> 16) & 0xff;
+		let payload = (code & 0xffff) as u16;
+		if code >> 32 != 0 || (code >> 24 != 1 && code >> 24 != 3 && payload != 0) {
+			return None;
+		}
+
+		Some(match (code >> 24, variant) {
+			(0, 0) => ExitSucceed::Stopped.into(),
+			(0, 1) => ExitSucceed::Returned.into(),
+			(0, 2) => ExitSucceed::Suicided.into(),
+			(1, 0) => ExitError::from_code(payload)?.into(),
+			(2, 0) => ExitRevert::Reverted.into(),
+			(3, 0) if payload == 0 => ExitFatal::NotSupported.into(),
+			(3, 1) if payload == 0 => ExitFatal::UnhandledInterrupt.into(),
+			(3, 2) => ExitFatal::CallErrorAsFatal(ExitError::from_code(payload)?).into(),
+			(3, 3) if payload == 0 => ExitFatal::Other(Cow::Borrowed("")).into(),
+			_ => return None,
+		})
+	}
 }
 
 /// Exit succeed reason.
@@ -124,7 +168,7 @@ pub enum ExitError {
 	/// Create opcode encountered collision (runtime).
 	#[cfg_attr(feature = "with-codec", codec(index = 6))]
 	CreateCollision,
-	/// Create init code exceeds limit (runtime).
+	/// Deployed code of a create exceeds limit (runtime).
 	#[cfg_attr(feature = "with-codec", codec(index = 7))]
 	CreateContractLimit,
 	/// Invalid opcode during execution or starting byte is 0xef. See [EIP-3541](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-3541.md).
@@ -151,11 +195,87 @@ pub enum ExitError {
 	#[cfg_attr(feature = "with-codec", codec(index = 12))]
 	CreateEmpty,
 
+	/// Call data exceeds the configured limit (runtime).
+	#[cfg_attr(feature = "with-codec", codec(index = 14))]
+	CallDataTooLarge,
+	/// Return data exceeds the configured limit (runtime).
+	#[cfg_attr(feature = "with-codec", codec(index = 16))]
+	ReturnDataTooLarge,
+	/// Transaction sender has code deployed. See [EIP-3607](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-3607.md).
+	#[cfg_attr(feature = "with-codec", codec(index = 17))]
+	SenderNotEOA,
+	/// Account nonce would exceed `u64::MAX`. See [EIP-2681](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-2681.md).
+	#[cfg_attr(feature = "with-codec", codec(index = 18))]
+	MaxNonce,
+	/// Create init code exceeds the configured limit. See [EIP-3860](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-3860.md).
+	#[cfg_attr(feature = "with-codec", codec(index = 19))]
+	InitcodeTooLarge,
+
 	/// Other normal errors.
 	#[cfg_attr(feature = "with-codec", codec(index = 13))]
 	Other(Cow<'static, str>),
 }
 
+impl ExitError {
+	/// Codec index of the variant in the high byte, and the opcode of
+	/// `InvalidCode` in the low byte.
+	fn to_code(&self) -> u16 {
+		match self {
+			Self::StackUnderflow => 0,
+			Self::StackOverflow => 1 << 8,
+			Self::InvalidJump => 2 << 8,
+			Self::InvalidRange => 3 << 8,
+			Self::DesignatedInvalid => 4 << 8,
+			Self::CallTooDeep => 5 << 8,
+			Self::CreateCollision => 6 << 8,
+			Self::CreateContractLimit => 7 << 8,
+			Self::OutOfOffset => 8 << 8,
+			Self::OutOfGas => 9 << 8,
+			Self::OutOfFund => 10 << 8,
+			Self::PCUnderflow => 11 << 8,
+			Self::CreateEmpty => 12 << 8,
+			Self::Other(_) => 13 << 8,
+			Self::CallDataTooLarge => 14 << 8,
+			Self::InvalidCode(opcode) => 15 << 8 | u16::from(opcode.0),
+			Self::ReturnDataTooLarge => 16 << 8,
+			Self::SenderNotEOA => 17 << 8,
+			Self::MaxNonce => 18 << 8,
+			Self::InitcodeTooLarge => 19 << 8,
+		}
+	}
+
+	fn from_code(code: u16) -> Option<Self> {
+		let opcode = (code & 0xff) as u8;
+		if opcode != 0 && code >> 8 != 15 {
+			return None;
+		}
+
+		Some(match code >> 8 {
+			0 => Self::StackUnderflow,
+			1 => Self::StackOverflow,
+			2 => Self::InvalidJump,
+			3 => Self::InvalidRange,
+			4 => Self::DesignatedInvalid,
+			5 => Self::CallTooDeep,
+			6 => Self::CreateCollision,
+			7 => Self::CreateContractLimit,
+			8 => Self::OutOfOffset,
+			9 => Self::OutOfGas,
+			10 => Self::OutOfFund,
+			11 => Self::PCUnderflow,
+			12 => Self::CreateEmpty,
+			13 => Self::Other(Cow::Borrowed("")),
+			14 => Self::CallDataTooLarge,
+			15 => Self::InvalidCode(Opcode(opcode)),
+			16 => Self::ReturnDataTooLarge,
+			17 => Self::SenderNotEOA,
+			18 => Self::MaxNonce,
+			19 => Self::InitcodeTooLarge,
+			_ => return None,
+		})
+	}
+}
+
 impl From<ExitError> for ExitReason {
 	fn from(s: ExitError) -> Self {
 		Self::Error(s)
@@ -186,3 +306,35 @@ impl From<ExitFatal> for ExitReason {
 		Self::Fatal(s)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use crate::{ExitError, ExitFatal, ExitReason, ExitRevert, ExitSucceed, Opcode};
+
+	#[test]
+	fn exit_reason_status_code_round_trip() {
+		let reasons: [ExitReason; 10] = [
+			ExitSucceed::Stopped.into(),
+			ExitSucceed::Suicided.into(),
+			ExitError::OutOfGas.into(),
+			ExitError::InvalidCode(Opcode::INVALID).into(),
+			ExitError::SenderNotEOA.into(),
+			ExitError::MaxNonce.into(),
+			ExitError::InitcodeTooLarge.into(),
+			ExitRevert::Reverted.into(),
+			ExitFatal::UnhandledInterrupt.into(),
+			ExitFatal::CallErrorAsFatal(ExitError::InvalidCode(Opcode(0x0c))).into(),
+		];
+
+		for reason in reasons.iter() {
+			assert_eq!(ExitReason::from_u64(reason.to_u64()).as_ref(), Some(reason));
+		}
+
+		assert_eq!(
+			ExitReason::from_u64(ExitReason::from(ExitError::Other("boom".into())).to_u64()),
+			Some(ExitError::Other("".into()).into())
+		);
+		assert_eq!(ExitReason::from_u64(1 << 24 | 99 << 8), None);
+		assert_eq!(ExitReason::from_u64(2 << 24 | 1), None);
+	}
+}
@@ -22,13 +22,23 @@ macro_rules! pop_u256 {
 	( $machine:expr, $( $x:ident ),* ) => (
 		$(
 			let $x = match $machine.stack.pop() {
-				Ok(value) => U256::from_big_endian(&value[..]),
+				Ok(value) => crate::utils::be_bytes_to_u256(&value.0),
 				Err(e) => return Control::Exit(e.into()),
 			};
 		)*
 	);
 }
 
+macro_rules! pop_peek_u256 {
+	( $machine:expr, $pop:ident, $peek:ident ) => {
+		pop_u256!($machine, $pop);
+		let $peek = match $machine.stack.peek(0) {
+			Ok(value) => crate::utils::be_bytes_to_u256(&value.0),
+			Err(e) => return Control::Exit(e.into()),
+		};
+	};
+}
+
 macro_rules! push {
 	( $machine:expr, $( $x:expr ),* ) => (
 		$(
@@ -43,8 +53,7 @@ macro_rules! push {
 macro_rules! push_u256 {
 	( $machine:expr, $( $x:expr ),* ) => (
 		$(
-			let mut value = H256::default();
-			$x.to_big_endian(&mut value[..]);
+			let value = H256(crate::utils::u256_to_be_bytes($x));
 			match $machine.stack.push(value) {
 				Ok(()) => (),
 				Err(e) => return Control::Exit(e.into()),
@@ -53,11 +62,22 @@ macro_rules! push_u256 {
 	)
 }
 
+macro_rules! replace_top_u256 {
+	( $machine:expr, $x:expr ) => {
+		try_or_fail!($machine
+			.stack
+			.replace_top(H256(crate::utils::u256_to_be_bytes($x))));
+	};
+}
+
 macro_rules! op1_u256_fn {
 	( $machine:expr, $op:path ) => {{
-		pop_u256!($machine, op1);
+		let op1 = match $machine.stack.peek(0) {
+			Ok(value) => crate::utils::be_bytes_to_u256(&value.0),
+			Err(e) => return Control::Exit(e.into()),
+		};
 		let ret = $op(op1);
-		push_u256!($machine, ret);
+		replace_top_u256!($machine, ret);
 
 		Control::Continue(1)
 	}};
@@ -65,9 +85,9 @@ macro_rules! op1_u256_fn {
 
 macro_rules! op2_u256_bool_ref {
 	( $machine:expr, $op:ident ) => {{
-		pop_u256!($machine, op1, op2);
+		pop_peek_u256!($machine, op1, op2);
 		let ret = op1.$op(&op2);
-		push_u256!($machine, if ret { U256::one() } else { U256::zero() });
+		replace_top_u256!($machine, if ret { U256::one() } else { U256::zero() });
 
 		Control::Continue(1)
 	}};
@@ -75,9 +95,9 @@ macro_rules! op2_u256_bool_ref {
 
 macro_rules! op2_u256 {
 	( $machine:expr, $op:ident ) => {{
-		pop_u256!($machine, op1, op2);
+		pop_peek_u256!($machine, op1, op2);
 		let ret = op1.$op(op2);
-		push_u256!($machine, ret);
+		replace_top_u256!($machine, ret);
 
 		Control::Continue(1)
 	}};
@@ -85,9 +105,9 @@ macro_rules! op2_u256 {
 
 macro_rules! op2_u256_tuple {
 	( $machine:expr, $op:ident ) => {{
-		pop_u256!($machine, op1, op2);
+		pop_peek_u256!($machine, op1, op2);
 		let (ret, ..) = op1.$op(op2);
-		push_u256!($machine, ret);
+		replace_top_u256!($machine, ret);
 
 		Control::Continue(1)
 	}};
@@ -95,19 +115,39 @@ macro_rules! op2_u256_tuple {
 
 macro_rules! op2_u256_fn {
 	( $machine:expr, $op:path ) => {{
-		pop_u256!($machine, op1, op2);
+		pop_peek_u256!($machine, op1, op2);
 		let ret = $op(op1, op2);
-		push_u256!($machine, ret);
+		replace_top_u256!($machine, ret);
 
 		Control::Continue(1)
 	}};
 }
 
-macro_rules! op3_u256_fn {
-	( $machine:expr, $op:path ) => {{
-		pop_u256!($machine, op1, op2, op3);
-		let ret = $op(op1, op2, op3);
-		push_u256!($machine, ret);
+/// Generic stack operation with fixed input and output arity. The stack is
+/// checked for underflow and overflow before anything is popped, so `$op` is
+/// only invoked when the whole operation can complete. `$op` takes the inputs
+/// as `[U256; $inputs]` with the top of the stack first, and returns
+/// `[U256; $outputs]`, of which the first element ends up on top.
+macro_rules! stack_op {
+	( $machine:expr, $inputs:literal => $outputs:literal, $op:expr ) => {{
+		let len = $machine.stack.len();
+		if len < $inputs {
+			return Control::Exit(ExitError::StackUnderflow.into());
+		}
+		if len - $inputs + $outputs > $machine.stack.limit() {
+			return Control::Exit(ExitError::StackOverflow.into());
+		}
+
+		let mut inputs = [U256::zero(); $inputs];
+		for input in inputs.iter_mut() {
+			*input = crate::utils::be_bytes_to_u256(&try_or_fail!($machine.stack.pop()).0);
+		}
+		let outputs: [U256; $outputs] = $op(inputs);
+		for output in outputs.iter().rev() {
+			try_or_fail!($machine
+				.stack
+				.push(H256(crate::utils::u256_to_be_bytes(*output))));
+		}
 
 		Control::Continue(1)
 	}};
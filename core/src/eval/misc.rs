@@ -1,5 +1,5 @@
 use super::Control;
-use crate::{ExitError, ExitFatal, ExitRevert, ExitSucceed, Machine};
+use crate::{ExitError, ExitRevert, ExitSucceed, Machine};
 use core::cmp::min;
 use primitive_types::{H256, U256};
 
@@ -34,7 +34,7 @@ pub fn calldataload(state: &mut Machine) -> Control {
 		if let Some(p) = index.checked_add(U256::from(i)) {
 			if p <= U256::from(usize::MAX) {
 				let p = p.as_usize();
-				if p < state.data.len() {
+				if p < state.data_len {
 					load[i] = state.data[p];
 				}
 			}
@@ -47,7 +47,7 @@ pub fn calldataload(state: &mut Machine) -> Control {
 
 #[inline]
 pub fn calldatasize(state: &mut Machine) -> Control {
-	let len = U256::from(state.data.len());
+	let len = U256::from(state.data_len);
 	push_u256!(state, len);
 	Control::Continue(1)
 }
@@ -79,9 +79,9 @@ pub fn pop(state: &mut Machine) -> Control {
 #[inline]
 pub fn mload(state: &mut Machine) -> Control {
 	pop_u256!(state, index);
+	let offset = as_usize_or_fail!(index, ExitError::OutOfOffset);
 	try_or_fail!(state.memory.resize_offset(index, U256::from(32)));
-	let index = as_usize_or_fail!(index);
-	let value = H256::from_slice(&state.memory.get(index, 32)[..]);
+	let value = state.memory.load_word(offset);
 	push!(state, value);
 	Control::Continue(1)
 }
@@ -90,9 +90,9 @@ pub fn mload(state: &mut Machine) -> Control {
 pub fn mstore(state: &mut Machine) -> Control {
 	pop_u256!(state, index);
 	pop!(state, value);
+	let offset = as_usize_or_fail!(index, ExitError::OutOfOffset);
 	try_or_fail!(state.memory.resize_offset(index, U256::from(32)));
-	let index = as_usize_or_fail!(index);
-	match state.memory.set(index, &value[..], Some(32)) {
+	match state.memory.set(offset, &value[..], Some(32)) {
 		Ok(()) => Control::Continue(1),
 		Err(e) => Control::Exit(e.into()),
 	}
@@ -101,10 +101,10 @@ pub fn mstore(state: &mut Machine) -> Control {
 #[inline]
 pub fn mstore8(state: &mut Machine) -> Control {
 	pop_u256!(state, index, value);
+	let offset = as_usize_or_fail!(index, ExitError::OutOfOffset);
 	try_or_fail!(state.memory.resize_offset(index, U256::one()));
-	let index = as_usize_or_fail!(index);
 	let value = (value.low_u32() & 0xff) as u8;
-	match state.memory.set(index, &[value], Some(1)) {
+	match state.memory.set(offset, &[value], Some(1)) {
 		Ok(()) => Control::Continue(1),
 		Err(e) => Control::Exit(e.into()),
 	}
@@ -159,7 +159,7 @@ pub fn push(state: &mut Machine, n: usize, position: usize) -> Control {
 	val[(32 - n)..(32 - n + slice.len())].copy_from_slice(slice);
 
 	push!(state, H256(val));
-	Control::Continue(1 + n)
+	Control::Continue(end - position)
 }
 
 #[inline]
@@ -49,11 +49,15 @@ fn eval_smod(state: &mut Machine, _opcode: Opcode, _position: usize) -> Control
 }
 
 fn eval_addmod(state: &mut Machine, _opcode: Opcode, _position: usize) -> Control {
-	op3_u256_fn!(state, self::arithmetic::addmod)
+	stack_op!(state, 3 => 1, |[op1, op2, op3]: [U256; 3]| [
+		self::arithmetic::addmod(op1, op2, op3)
+	])
 }
 
 fn eval_mulmod(state: &mut Machine, _opcode: Opcode, _position: usize) -> Control {
-	op3_u256_fn!(state, self::arithmetic::mulmod)
+	stack_op!(state, 3 => 1, |[op1, op2, op3]: [U256; 3]| [
+		self::arithmetic::mulmod(op1, op2, op3)
+	])
 }
 
 fn eval_exp(state: &mut Machine, _opcode: Opcode, _position: usize) -> Control {
@@ -570,3 +574,59 @@ pub fn eval(state: &mut Machine, opcode: Opcode, position: usize) -> Control {
 
 	TABLE[opcode.as_usize()](state, opcode, position)
 }
+
+#[cfg(test)]
+mod tests {
+	use super::Control;
+	use crate::{Capture, ExitError, Machine};
+	use alloc::rc::Rc;
+	use alloc::vec::Vec;
+	use core::cell::Cell;
+	use primitive_types::{H256, U256};
+
+	fn swap_three(machine: &mut Machine, called: &Cell<bool>) -> Control {
+		stack_op!(machine, 3 => 3, |[a, b, c]: [U256; 3]| {
+			called.set(true);
+			[c, b, a]
+		})
+	}
+
+	#[test]
+	fn stack_op_underflow_skips_op() {
+		let mut machine = Machine::new(Rc::new(Vec::new()), Rc::new(Vec::new()), 1024, 10000);
+		machine.stack_mut().push(H256::repeat_byte(0x01)).unwrap();
+		machine.stack_mut().push(H256::repeat_byte(0x02)).unwrap();
+
+		let called = Cell::new(false);
+		assert_eq!(
+			swap_three(&mut machine, &called),
+			Control::Exit(ExitError::StackUnderflow.into())
+		);
+		assert!(!called.get());
+		assert_eq!(machine.stack().len(), 2);
+
+		machine.stack_mut().push(H256::repeat_byte(0x03)).unwrap();
+		assert_eq!(swap_three(&mut machine, &called), Control::Continue(1));
+		assert!(called.get());
+		assert_eq!(machine.stack().peek(0), Ok(H256::repeat_byte(0x01)));
+		assert_eq!(machine.stack().peek(2), Ok(H256::repeat_byte(0x03)));
+	}
+
+	#[test]
+	fn memory_ops_reject_huge_offsets() {
+		// PUSH32 U256::MAX, followed by MLOAD, MSTORE or MSTORE8 with a value
+		// pushed first for the stores.
+		for (value, opcode) in [(false, 0x51), (true, 0x52), (true, 0x53)] {
+			let mut code = Vec::new();
+			if value {
+				code.extend_from_slice(&[0x60, 0x01]);
+			}
+			code.push(0x7f);
+			code.extend_from_slice(&[0xff; 32]);
+			code.push(opcode);
+
+			let mut machine = Machine::new(Rc::new(code), Rc::new(Vec::new()), 1024, 10000);
+			assert_eq!(machine.run(), Capture::Exit(ExitError::OutOfOffset.into()));
+		}
+	}
+}
@@ -0,0 +1,35 @@
+use crate::Opcode;
+
+/// Number of times each opcode was executed by a machine.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OpcodeHistogram([u64; 256]);
+
+impl OpcodeHistogram {
+	/// Create a histogram with all counts at zero.
+	pub fn new() -> Self {
+		Self([0; 256])
+	}
+
+	/// Count one execution of `opcode`.
+	#[inline]
+	pub fn record(&mut self, opcode: Opcode) {
+		let count = &mut self.0[opcode.as_usize()];
+		*count = count.saturating_add(1);
+	}
+
+	/// Number of times `opcode` was executed.
+	pub fn count(&self, opcode: Opcode) -> u64 {
+		self.0[opcode.as_usize()]
+	}
+
+	/// Counts of all opcodes, indexed by opcode byte.
+	pub fn counts(&self) -> &[u64; 256] {
+		&self.0
+	}
+}
+
+impl Default for OpcodeHistogram {
+	fn default() -> Self {
+		Self::new()
+	}
+}
@@ -1,40 +1,85 @@
 //! Core layer for EVM.
 
 #![deny(warnings)]
-#![forbid(unsafe_code, unused_variables, unused_imports)]
+#![forbid(unsafe_code, unused_variables)]
+// `environmental` allows unused imports in its expansion.
+#![cfg_attr(not(feature = "tracing"), forbid(unused_imports))]
 #![cfg_attr(not(feature = "std"), no_std)]
 
 extern crate alloc;
 extern crate core;
 
+#[cfg(feature = "tracing")]
+pub mod tracing;
+
+#[cfg(feature = "tracing")]
+macro_rules! event {
+	($x:expr) => {
+		use crate::tracing::Event::*;
+		crate::tracing::with(|listener| listener.event($x));
+	};
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! event {
+	($x:expr) => {};
+}
+
 mod error;
 mod eval;
+#[cfg(feature = "histogram")]
+mod histogram;
 mod memory;
 mod opcode;
 mod stack;
+mod trap;
 mod utils;
 mod valids;
 
 pub use crate::error::{Capture, ExitError, ExitFatal, ExitReason, ExitRevert, ExitSucceed, Trap};
+#[cfg(feature = "histogram")]
+pub use crate::histogram::OpcodeHistogram;
 pub use crate::memory::Memory;
 pub use crate::opcode::Opcode;
 pub use crate::stack::Stack;
+pub use crate::trap::TrapKind;
+pub use crate::utils::{
+	be_bytes_to_u256, has_explicit_terminator, rlp, u256_to_be_bytes, u256_to_minimal_be_bytes,
+};
 pub use crate::valids::Valids;
 
 use crate::eval::{eval, Control};
 use alloc::rc::Rc;
 use alloc::vec::Vec;
 use core::ops::Range;
-use primitive_types::U256;
+use primitive_types::{H256, U256};
+
+/// State of a paused `Machine`, as exported by `Machine::export_state`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MachineState {
+	/// Stack contents, bottom first.
+	pub stack: Vec<H256>,
+	/// Memory bytes written so far, which may be shorter than the effective
+	/// length.
+	pub memory: Vec<u8>,
+	/// Effective memory length, as seen by `MSIZE` and memory gas.
+	pub memory_effective_len: U256,
+	/// Program counter.
+	pub position: usize,
+}
 
 /// Core execution layer for EVM.
 pub struct Machine {
 	/// Program data.
 	data: Rc<Vec<u8>>,
+	/// Length of the program data, cached at construction.
+	data_len: usize,
 	/// Program code.
 	code: Rc<Vec<u8>>,
 	/// Program counter.
 	position: Result<usize, ExitReason>,
+	/// Program counter of the opcode that caused a non-succeed exit.
+	error_pc: Option<usize>,
 	/// Return value.
 	return_range: Range<U256>,
 	/// Code validity maps.
@@ -43,6 +88,9 @@ pub struct Machine {
 	memory: Memory,
 	/// Stack.
 	stack: Stack,
+	/// Executions per opcode.
+	#[cfg(feature = "histogram")]
+	histogram: OpcodeHistogram,
 }
 
 impl Machine {
@@ -66,6 +114,27 @@ impl Machine {
 	pub fn position(&self) -> &Result<usize, ExitReason> {
 		&self.position
 	}
+	/// Reference of the valid jump destinations of the code.
+	pub fn valids(&self) -> &Valids {
+		&self.valids
+	}
+	/// Reference of the call data. The buffer is shared with the machine and
+	/// stays borrowed for as long as the returned slice is alive, so partial
+	/// reads can be done without copying.
+	pub fn calldata(&self) -> &[u8] {
+		&self.data
+	}
+	/// Program counter of the opcode at which the machine exited with an
+	/// error, revert or fatal reason, if any.
+	pub fn error_pc(&self) -> Option<usize> {
+		self.error_pc
+	}
+
+	/// Number of times each opcode was executed.
+	#[cfg(feature = "histogram")]
+	pub fn histogram(&self) -> &OpcodeHistogram {
+		&self.histogram
+	}
 
 	/// Create a new machine with given code and data.
 	pub fn new(
@@ -77,13 +146,52 @@ impl Machine {
 		let valids = Valids::new(&code[..]);
 
 		Self {
+			data_len: data.len(),
 			data,
 			code,
 			position: Ok(0),
+			error_pc: None,
 			return_range: U256::zero()..U256::zero(),
 			valids,
 			memory: Memory::new(memory_limit),
 			stack: Stack::new(stack_limit),
+			#[cfg(feature = "histogram")]
+			histogram: OpcodeHistogram::new(),
+		}
+	}
+
+	/// Create a new machine with given code and data, starting from a
+	/// preloaded `stack`. Useful to step a single opcode in tests.
+	pub fn with_stack(
+		code: Rc<Vec<u8>>,
+		data: Rc<Vec<u8>>,
+		stack: Stack,
+		memory_limit: usize,
+	) -> Self {
+		let mut machine = Self::new(code, data, stack.limit(), memory_limit);
+		machine.stack = stack;
+		machine
+	}
+
+	/// Reset the machine to run `code` with `data` from the start, keeping its
+	/// stack and memory limits. The jump destination analysis is only redone
+	/// when `code` is not the same `Rc` as the current code.
+	pub fn reset(&mut self, code: Rc<Vec<u8>>, data: Rc<Vec<u8>>) {
+		if !Rc::ptr_eq(&self.code, &code) {
+			self.valids = Valids::new(&code[..]);
+			self.code = code;
+		}
+
+		self.data_len = data.len();
+		self.data = data;
+		self.position = Ok(0);
+		self.error_pc = None;
+		self.return_range = U256::zero()..U256::zero();
+		self.memory = Memory::new(self.memory.limit());
+		self.stack = Stack::new(self.stack.limit());
+		#[cfg(feature = "histogram")]
+		{
+			self.histogram = OpcodeHistogram::new();
 		}
 	}
 
@@ -127,6 +235,63 @@ impl Machine {
 		}
 	}
 
+	/// Resume the machine after a trap has been resolved by the host, pushing
+	/// the trap outputs onto the stack. Inputs of the trapped opcode are
+	/// expected to have been popped by the host already.
+	pub fn resume_trap(&mut self, outputs: Vec<H256>) -> Result<(), ExitError> {
+		if self.position.is_err() {
+			return Err(ExitError::Other("cannot resume an exited machine".into()));
+		}
+
+		for output in outputs {
+			self.stack.push(output)?;
+		}
+
+		Ok(())
+	}
+
+	/// Export the state of a paused machine, so it can be persisted and
+	/// restored later with `import_state`. Returns `None` if the machine has
+	/// already exited.
+	pub fn export_state(&self) -> Option<MachineState> {
+		let position = *self.position.as_ref().ok()?;
+
+		Some(MachineState {
+			stack: self.stack.data().clone(),
+			memory: self.memory.data().clone(),
+			memory_effective_len: self.memory.effective_len(),
+			position,
+		})
+	}
+
+	/// Restore a state exported with `export_state` into this machine, which
+	/// is expected to be freshly created with the same code and data. A
+	/// position past the end of code makes the next step exit with a fatal
+	/// error at that position.
+	pub fn import_state(&mut self, state: MachineState) -> Result<(), ExitReason> {
+		let MachineState {
+			stack,
+			memory,
+			memory_effective_len,
+			position,
+		} = state;
+
+		let mut new_stack = Stack::new(self.stack.limit());
+		for value in stack {
+			new_stack.push(value)?;
+		}
+
+		let mut new_memory = Memory::new(self.memory.limit());
+		new_memory.resize_end(memory_effective_len)?;
+		new_memory.set(0, &memory, None)?;
+
+		self.stack = new_stack;
+		self.memory = new_memory;
+		self.position = Ok(position);
+		self.error_pc = None;
+		Ok(())
+	}
+
 	/// Loop stepping the machine, until it stops.
 	pub fn run(&mut self) -> Capture<ExitReason, Trap> {
 		loop {
@@ -137,6 +302,60 @@ impl Machine {
 		}
 	}
 
+	/// Loop stepping the machine like `run`, but exit with a fatal reason once
+	/// `max_steps` opcodes have been executed without stopping.
+	pub fn run_bounded(&mut self, max_steps: usize) -> Capture<ExitReason, Trap> {
+		for _ in 0..max_steps {
+			match self.step() {
+				Ok(()) => (),
+				Err(res) => return res,
+			}
+		}
+
+		let reason: ExitReason = ExitFatal::Other("step limit reached".into()).into();
+		self.exit(reason.clone());
+		Capture::Exit(reason)
+	}
+
+	/// Loop stepping the machine without an executor, for static analysis.
+	///
+	/// External opcodes are treated as no-ops: their inputs are popped and
+	/// zeros are pushed for their outputs. `SUICIDE` exits with
+	/// `ExitSucceed::Suicided`. Opcodes unknown to the machine are still
+	/// returned as traps.
+	pub fn run_analysis(&mut self) -> Capture<ExitReason, Trap> {
+		loop {
+			let opcode = match self.step() {
+				Ok(()) => continue,
+				Err(Capture::Exit(reason)) => return Capture::Exit(reason),
+				Err(Capture::Trap(opcode)) => opcode,
+			};
+
+			let (inputs, outputs) = match opcode.external_stack_io() {
+				Some(io) => io,
+				None => return Capture::Trap(opcode),
+			};
+
+			let stack = &mut self.stack;
+			let result = (0..inputs)
+				.try_for_each(|_| stack.pop().map(|_| ()))
+				.and_then(|()| (0..outputs).try_for_each(|_| stack.push(H256::default())));
+
+			let reason: ExitReason = match result {
+				Ok(()) if opcode == Opcode::SUICIDE => ExitSucceed::Suicided.into(),
+				Ok(()) => continue,
+				Err(e) => {
+					if let Ok(position) = self.position {
+						self.error_pc = Some(position - 1);
+					}
+					e.into()
+				}
+			};
+			self.exit(reason.clone());
+			return Capture::Exit(reason);
+		}
+	}
+
 	#[inline]
 	/// Step the machine, executing one opcode. It then returns.
 	pub fn step(&mut self) -> Result<(), Capture<ExitReason, Trap>> {
@@ -146,24 +365,40 @@ impl Machine {
 			.map_err(|reason| Capture::Exit(reason.clone()))?;
 
 		match self.code.get(position).map(|v| Opcode(*v)) {
-			Some(opcode) => match eval(self, opcode, position) {
-				Control::Continue(p) => {
-					self.position = Ok(position + p);
-					Ok(())
-				}
-				Control::Exit(e) => {
-					self.position = Err(e.clone());
-					Err(Capture::Exit(e))
-				}
-				Control::Jump(p) => {
-					self.position = Ok(p);
-					Ok(())
-				}
-				Control::Trap(opcode) => {
-					self.position = Ok(position + 1);
-					Err(Capture::Trap(opcode))
+			Some(opcode) => {
+				#[cfg(feature = "histogram")]
+				self.histogram.record(opcode);
+
+				match eval(self, opcode, position) {
+					Control::Continue(p) => {
+						self.position = Ok(position + p);
+						Ok(())
+					}
+					Control::Exit(e) => {
+						if !e.is_succeed() {
+							self.error_pc = Some(position);
+						}
+						self.position = Err(e.clone());
+						Err(Capture::Exit(e))
+					}
+					Control::Jump(p) => {
+						self.position = Ok(p);
+						Ok(())
+					}
+					Control::Trap(opcode) => {
+						self.position = Ok(position + 1);
+						Err(Capture::Trap(opcode))
+					}
 				}
-			},
+			}
+			// Opcodes never move past the end of code, but an imported
+			// position may.
+			None if position > self.code.len() => {
+				let reason: ExitReason = ExitFatal::Other("position out of code".into()).into();
+				self.error_pc = Some(position);
+				self.position = Err(reason.clone());
+				Err(Capture::Exit(reason))
+			}
 			None => {
 				self.position = Err(ExitSucceed::Stopped.into());
 				Err(Capture::Exit(ExitSucceed::Stopped.into()))
@@ -171,3 +406,332 @@ impl Machine {
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use crate::{
+		Capture, ExitError, ExitFatal, ExitSucceed, Machine, MachineState, Opcode, Stack, Valids,
+	};
+	use alloc::rc::Rc;
+	use alloc::vec::Vec;
+	use primitive_types::{H256, U256};
+
+	#[test]
+	fn error_pc_of_invalid_jump() {
+		// PUSH1 0x05, JUMP
+		let code = Rc::new(vec![0x60, 0x05, 0x56]);
+		let mut machine = Machine::new(code, Rc::new(Vec::new()), 1024, 10000);
+
+		assert_eq!(machine.error_pc(), None);
+		assert_eq!(machine.run(), Capture::Exit(ExitError::InvalidJump.into()));
+		assert_eq!(machine.error_pc(), Some(2));
+	}
+
+	#[test]
+	fn run_analysis_through_call() {
+		// PUSH1 0 (x7), CALL, PUSH1 0, SSTORE, STOP
+		let mut code = [0x60, 0x00].repeat(7);
+		code.extend_from_slice(&[0xf1, 0x60, 0x00, 0x55, 0x00]);
+		let mut machine = Machine::new(Rc::new(code), Rc::new(Vec::new()), 1024, 10000);
+
+		assert_eq!(
+			machine.run_analysis(),
+			Capture::Exit(ExitSucceed::Stopped.into())
+		);
+		assert!(machine.stack().is_empty());
+	}
+
+	#[test]
+	fn resume_trap_after_sha3() {
+		// PUSH1 0, PUSH1 0, SHA3, PUSH1 0, MSTORE, PUSH1 32, PUSH1 0, RETURN
+		let code = vec![
+			0x60, 0x00, 0x60, 0x00, 0x20, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3,
+		];
+		let mut machine = Machine::new(Rc::new(code), Rc::new(Vec::new()), 1024, 10000);
+
+		assert_eq!(machine.run(), Capture::Trap(Opcode::SHA3));
+		machine.stack_mut().pop().unwrap();
+		machine.stack_mut().pop().unwrap();
+
+		let hash = H256::repeat_byte(0x11);
+		assert_eq!(machine.resume_trap(vec![hash]), Ok(()));
+		assert_eq!(machine.run(), Capture::Exit(ExitSucceed::Returned.into()));
+		assert_eq!(machine.return_value(), hash.as_bytes());
+		assert!(machine.resume_trap(Vec::new()).is_err());
+	}
+
+	#[test]
+	fn calldata_disjoint_reads() {
+		let data = Rc::new((0u8..64).collect::<Vec<_>>());
+		let machine = Machine::new(Rc::new(Vec::new()), data.clone(), 1024, 10000);
+
+		let calldata = machine.calldata();
+		assert_eq!(calldata.as_ptr(), data.as_ptr());
+		assert_eq!(&calldata[0..4], &[0, 1, 2, 3]);
+		assert_eq!(&calldata[32..36], &[32, 33, 34, 35]);
+	}
+
+	#[test]
+	fn export_import_round_trip() {
+		// PUSH1 0x2a, PUSH1 0, MSTORE, PUSH1 32, PUSH1 0, SHA3, PUSH1 32, PUSH1 0, RETURN
+		let code = Rc::new(vec![
+			0x60, 0x2a, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0x20, 0x60, 0x20, 0x60, 0x00,
+			0xf3,
+		]);
+		let mut machine = Machine::new(code.clone(), Rc::new(Vec::new()), 1024, 10000);
+		assert_eq!(machine.run(), Capture::Trap(Opcode::SHA3));
+
+		let state = machine.export_state().unwrap();
+		assert_eq!(state.stack.len(), 2);
+		assert_eq!(state.memory.len(), 32);
+		assert_eq!(state.memory_effective_len, U256::from(32));
+		assert_eq!(state.position, 10);
+
+		let mut restored = Machine::new(code, Rc::new(Vec::new()), 1024, 10000);
+		assert_eq!(restored.import_state(state), Ok(()));
+		assert_eq!(restored.export_state(), machine.export_state());
+		assert_eq!(restored.run(), machine.run());
+		assert_eq!(restored.return_value(), machine.return_value());
+		assert_eq!(restored.export_state(), None);
+	}
+
+	#[test]
+	fn export_state_skips_unwritten_memory() {
+		// PUSH5 0x0100000000, MLOAD, POP, PUSH1 0, PUSH1 0, SHA3: reads far
+		// past `memory_limit` without writing anything, then traps.
+		let code = Rc::new(vec![
+			0x64, 0x01, 0x00, 0x00, 0x00, 0x00, 0x51, 0x50, 0x60, 0x00, 0x60, 0x00, 0x20,
+		]);
+		let mut machine = Machine::new(code.clone(), Rc::new(Vec::new()), 1024, 10000);
+		assert_eq!(machine.run(), Capture::Trap(Opcode::SHA3));
+
+		let state = machine.export_state().unwrap();
+		assert!(state.memory.is_empty());
+		assert_eq!(state.memory_effective_len, U256::from(0x1_0000_0020u64));
+
+		let mut restored = Machine::new(code, Rc::new(Vec::new()), 1024, 10000);
+		assert_eq!(restored.import_state(state.clone()), Ok(()));
+		assert_eq!(
+			restored.memory().effective_len(),
+			state.memory_effective_len
+		);
+		assert_eq!(restored.export_state(), Some(state));
+	}
+
+	#[test]
+	fn run_bounded_halts_infinite_loop() {
+		// JUMPDEST, PUSH1 0, JUMP
+		let code = Rc::new(vec![0x5b, 0x60, 0x00, 0x56]);
+		let mut machine = Machine::new(code, Rc::new(Vec::new()), 1024, 10000);
+
+		let reason = Capture::Exit(ExitFatal::Other("step limit reached".into()).into());
+		assert_eq!(machine.run_bounded(1000), reason);
+		assert_eq!(machine.step(), Err(reason));
+	}
+
+	#[test]
+	fn calldatasize_is_cached() {
+		// CALLDATASIZE, PUSH1 0, MSTORE, PUSH1 32, PUSH1 0, RETURN
+		let code = Rc::new(vec![0x36, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3]);
+		let mut machine = Machine::new(code.clone(), Rc::new(vec![0xaa; 37]), 1024, 10000);
+		assert_eq!(machine.run(), Capture::Exit(ExitSucceed::Returned.into()));
+		assert_eq!(machine.return_value(), H256::from_low_u64_be(37).as_bytes());
+
+		// The cached length follows the data on reset.
+		machine.reset(code, Rc::new(vec![0xaa; 5]));
+		assert_eq!(machine.run(), Capture::Exit(ExitSucceed::Returned.into()));
+		assert_eq!(machine.return_value(), H256::from_low_u64_be(5).as_bytes());
+
+		// PUSH1 3, CALLDATALOAD, PUSH1 0, MSTORE, PUSH1 32, PUSH1 0, RETURN:
+		// reads zeros past the cached length.
+		let code = Rc::new(vec![
+			0x60, 0x03, 0x35, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3,
+		]);
+		let mut machine = Machine::new(code, Rc::new(vec![0xaa; 5]), 1024, 10000);
+		assert_eq!(machine.run(), Capture::Exit(ExitSucceed::Returned.into()));
+		let mut expected = [0; 32];
+		expected[..2].copy_from_slice(&[0xaa, 0xaa]);
+		assert_eq!(machine.return_value(), expected);
+	}
+
+	#[test]
+	fn position_past_code_is_fatal() {
+		// PUSH32 with only two bytes of immediate data.
+		let code = Rc::new(vec![0x7f, 0x01, 0x02]);
+		let mut machine = Machine::new(code.clone(), Rc::new(Vec::new()), 1024, 10000);
+		assert_eq!(machine.run(), Capture::Exit(ExitSucceed::Stopped.into()));
+		assert_eq!(machine.error_pc(), None);
+
+		let mut machine = Machine::new(code, Rc::new(Vec::new()), 1024, 10000);
+		machine
+			.import_state(MachineState {
+				stack: Vec::new(),
+				memory: Vec::new(),
+				memory_effective_len: U256::zero(),
+				position: usize::MAX,
+			})
+			.unwrap();
+		assert_eq!(machine.error_pc(), None);
+		assert_eq!(
+			machine.run(),
+			Capture::Exit(ExitFatal::Other("position out of code".into()).into())
+		);
+		assert_eq!(machine.error_pc(), Some(usize::MAX));
+	}
+
+	#[test]
+	fn valids_match_code_analysis() {
+		// JUMPDEST, PUSH1 0x5B, JUMPDEST, STOP
+		let code = vec![0x5b, 0x60, 0x5b, 0x5b, 0x00];
+		let machine = Machine::new(Rc::new(code.clone()), Rc::new(Vec::new()), 1024, 10000);
+
+		assert_eq!(machine.valids(), &Valids::new(&code));
+		for position in 0..=code.len() {
+			assert_eq!(
+				machine.valids().is_valid(position),
+				Valids::new(&code).is_valid(position)
+			);
+		}
+	}
+
+	#[cfg(feature = "tracing")]
+	#[test]
+	fn memory_grow_events() {
+		use crate::tracing::{using, Event, EventListener};
+
+		struct Growths(Vec<(usize, usize)>);
+
+		impl EventListener for Growths {
+			fn event(&mut self, event: Event) {
+				let Event::MemoryGrow { from, to } = event;
+				self.0.push((from, to));
+			}
+		}
+
+		// MSTORE(0, 1), MSTORE(64, 1), MSTORE(0, 1), STOP
+		let code = vec![
+			0x60, 0x01, 0x60, 0x00, 0x52, 0x60, 0x01, 0x60, 0x40, 0x52, 0x60, 0x01, 0x60, 0x00,
+			0x52, 0x00,
+		];
+		let mut machine = Machine::new(Rc::new(code), Rc::new(Vec::new()), 1024, 10000);
+		let mut growths = Growths(Vec::new());
+
+		let reason = using(&mut growths, || machine.run());
+		assert_eq!(reason, Capture::Exit(ExitSucceed::Stopped.into()));
+		assert_eq!(growths.0, vec![(0, 32), (32, 96)]);
+	}
+
+	#[test]
+	fn copy_from_past_the_end_zero_fills() {
+		// MSTORE(0, 0xff..ff), then `opcode` copies 32 bytes from `source_offset`
+		// over it.
+		let copy_over_word = |opcode: Opcode, source_offset: H256, data: Vec<u8>| {
+			let mut code = vec![0x7f];
+			code.extend_from_slice(&[0xff; 32]);
+			code.extend_from_slice(&[0x60, 0x00, 0x52, 0x60, 0x20, 0x7f]);
+			code.extend_from_slice(&source_offset[..]);
+			code.extend_from_slice(&[0x60, 0x00, opcode.as_u8(), 0x00]);
+
+			let mut machine = Machine::new(Rc::new(code), Rc::new(data), 1024, 10000);
+			assert_eq!(machine.run(), Capture::Exit(ExitSucceed::Stopped.into()));
+			machine.memory().get(0, 32)
+		};
+
+		let just_past_end = H256::from_low_u64_be(80);
+		let huge = H256::repeat_byte(0xff);
+		for offset in [just_past_end, huge].iter() {
+			assert_eq!(
+				copy_over_word(Opcode::CODECOPY, *offset, Vec::new()),
+				vec![0; 32]
+			);
+			assert_eq!(
+				copy_over_word(Opcode::CALLDATACOPY, *offset, vec![0x11; 4]),
+				vec![0; 32]
+			);
+		}
+	}
+
+	#[test]
+	fn zero_length_return_and_revert() {
+		// PUSH1 0, PUSH2 0x1000, RETURN / REVERT
+		for opcode in [Opcode::RETURN, Opcode::REVERT].iter() {
+			let code = vec![0x60, 0x00, 0x61, 0x10, 0x00, opcode.as_u8()];
+			let mut machine = Machine::new(Rc::new(code), Rc::new(Vec::new()), 1024, 10000);
+
+			assert!(matches!(machine.run(), Capture::Exit(_)));
+			assert_eq!(machine.return_value(), Vec::<u8>::new());
+			assert!(machine.memory().effective_len().is_zero());
+		}
+	}
+
+	#[test]
+	fn reset_keeps_jumps_valid() {
+		// PUSH1 0x04, JUMP, INVALID, JUMPDEST, STOP
+		let code = Rc::new(vec![0x60, 0x04, 0x56, 0xfe, 0x5b, 0x00]);
+		let mut machine = Machine::new(code.clone(), Rc::new(Vec::new()), 1024, 10000);
+		assert_eq!(machine.run(), Capture::Exit(ExitSucceed::Stopped.into()));
+
+		machine.reset(code, Rc::new(vec![0x01]));
+		assert_eq!(machine.position(), &Ok(0));
+		assert_eq!(machine.calldata(), &[0x01]);
+		assert_eq!(machine.run(), Capture::Exit(ExitSucceed::Stopped.into()));
+
+		// PUSH1 0x05, JUMP, INVALID, INVALID, JUMPDEST, STOP
+		let other = Rc::new(vec![0x60, 0x05, 0x56, 0xfe, 0xfe, 0x5b, 0x00]);
+		machine.reset(other.clone(), Rc::new(Vec::new()));
+		assert_eq!(machine.valids(), &Valids::new(&other));
+		assert_eq!(machine.run(), Capture::Exit(ExitSucceed::Stopped.into()));
+	}
+
+	#[cfg(feature = "histogram")]
+	#[test]
+	fn opcode_histogram() {
+		// PUSH1 3
+		// loop: JUMPDEST, PUSH1 1, SWAP1, SUB, DUP1, ISZERO, PUSH1 done, JUMPI,
+		//       PUSH1 loop, JUMP
+		// done: JUMPDEST, STOP
+		let code = vec![
+			0x60, 0x03, 0x5b, 0x60, 0x01, 0x90, 0x03, 0x80, 0x15, 0x60, 0x0f, 0x57, 0x60, 0x02,
+			0x56, 0x5b, 0x00,
+		];
+		let mut machine = Machine::new(Rc::new(code), Rc::new(Vec::new()), 1024, 10000);
+		assert_eq!(machine.run(), Capture::Exit(ExitSucceed::Stopped.into()));
+
+		let histogram = machine.histogram();
+		assert_eq!(histogram.count(Opcode::JUMPDEST), 4);
+		assert_eq!(histogram.count(Opcode::JUMP), 2);
+		assert_eq!(histogram.count(Opcode::JUMPI), 3);
+		assert_eq!(histogram.count(Opcode::SUB), 3);
+		assert_eq!(histogram.count(Opcode::STOP), 1);
+		assert_eq!(
+			histogram.counts().iter().sum::<u64>(),
+			1 + 3 * 8 + 2 * 2 + 2
+		);
+	}
+
+	#[test]
+	fn step_with_preloaded_stack() {
+		let mut stack = Stack::new(1024);
+		stack.push(H256::from_low_u64_be(3)).unwrap();
+		stack.push(H256::from_low_u64_be(4)).unwrap();
+		let code = Rc::new(vec![Opcode::ADD.as_u8()]);
+		let mut machine = Machine::with_stack(code, Rc::new(Vec::new()), stack, 10000);
+
+		assert_eq!(machine.step(), Ok(()));
+		assert_eq!(machine.stack().len(), 1);
+		assert_eq!(machine.stack().peek(0), Ok(H256::from_low_u64_be(7)));
+	}
+
+	#[test]
+	fn msize_is_word_aligned() {
+		// MSTORE8(0, 1), MSIZE, MSTORE8(40, 1), MSIZE, STOP
+		let code = vec![
+			0x60, 0x01, 0x60, 0x00, 0x53, 0x59, 0x60, 0x01, 0x60, 0x28, 0x53, 0x59, 0x00,
+		];
+		let mut machine = Machine::new(Rc::new(code), Rc::new(Vec::new()), 1024, 10000);
+
+		assert_eq!(machine.run(), Capture::Exit(ExitSucceed::Stopped.into()));
+		assert_eq!(machine.stack().peek(1), Ok(H256::from_low_u64_be(32)));
+		assert_eq!(machine.stack().peek(0), Ok(H256::from_low_u64_be(64)));
+	}
+}
@@ -2,7 +2,7 @@ use crate::{ExitError, ExitFatal};
 use alloc::vec::Vec;
 use core::cmp::min;
 use core::ops::{BitAnd, Not};
-use primitive_types::U256;
+use primitive_types::{H256, U256};
 
 /// A sequencial memory. It uses Rust's `Vec` for internal
 /// representation.
@@ -67,6 +67,10 @@ impl Memory {
 	pub fn resize_end(&mut self, end: U256) -> Result<(), ExitError> {
 		if end > self.effective_len {
 			let new_end = next_multiple_of_32(end).ok_or(ExitError::InvalidRange)?;
+			event!(MemoryGrow {
+				from: saturating_usize(self.effective_len),
+				to: saturating_usize(new_end),
+			});
 			self.effective_len = new_end;
 		}
 
@@ -96,6 +100,18 @@ impl Memory {
 		ret
 	}
 
+	/// Get the 32-byte word at given offset, zero-padded past the end of the
+	/// memory.
+	pub fn load_word(&self, offset: usize) -> H256 {
+		let mut word = H256::default();
+		if offset < self.data.len() {
+			let end = min(offset.saturating_add(32), self.data.len());
+			word.0[..end - offset].copy_from_slice(&self.data[offset..end]);
+		}
+
+		word
+	}
+
 	/// Set memory region at given offset. The offset and value is considered
 	/// untrusted.
 	pub fn set(
@@ -183,6 +199,15 @@ impl Memory {
 	}
 }
 
+#[cfg(feature = "tracing")]
+fn saturating_usize(x: U256) -> usize {
+	if x > U256::from(usize::MAX) {
+		usize::MAX
+	} else {
+		x.as_usize()
+	}
+}
+
 /// Rounds up `x` to the closest multiple of 32. If `x % 32 == 0` then `x` is returned.
 #[inline]
 fn next_multiple_of_32(x: U256) -> Option<U256> {
@@ -192,7 +217,7 @@ fn next_multiple_of_32(x: U256) -> Option<U256> {
 
 #[cfg(test)]
 mod tests {
-	use super::{next_multiple_of_32, U256};
+	use super::{next_multiple_of_32, Memory, H256, U256};
 
 	#[test]
 	fn test_next_multiple_of_32() {
@@ -225,4 +250,18 @@ mod tests {
 			}
 		}
 	}
+
+	#[test]
+	fn load_word() {
+		let mut memory = Memory::new(10000);
+		assert_eq!(memory.load_word(64), H256::default());
+
+		memory.set(0, &[0x11; 40], None).unwrap();
+		assert_eq!(memory.load_word(0), H256::repeat_byte(0x11));
+
+		let mut partial = H256::default();
+		partial.0[..8].copy_from_slice(&[0x11; 8]);
+		assert_eq!(memory.load_word(32), partial);
+		assert_eq!(memory.load_word(usize::MAX), H256::default());
+	}
 }
@@ -188,6 +188,10 @@ impl Opcode {
 	pub const SELFBALANCE: Opcode = Opcode(0x47);
 	/// `BASEFEE`
 	pub const BASEFEE: Opcode = Opcode(0x48);
+	/// `BLOBHASH`
+	pub const BLOBHASH: Opcode = Opcode(0x49);
+	/// `BLOBBASEFEE`
+	pub const BLOBBASEFEE: Opcode = Opcode(0x4a);
 	/// `ORIGIN`
 	pub const ORIGIN: Opcode = Opcode(0x32);
 	/// `CALLER`
@@ -216,6 +220,8 @@ impl Opcode {
 	pub const NUMBER: Opcode = Opcode(0x43);
 	/// `DIFFICULTY`
 	pub const DIFFICULTY: Opcode = Opcode(0x44);
+	/// `PREVRANDAO`, replacing `DIFFICULTY` after the merge
+	pub const PREVRANDAO: Opcode = Opcode(0x44);
 	/// `GASLIMIT`
 	pub const GASLIMIT: Opcode = Opcode(0x45);
 	/// `SLOAD`
@@ -259,6 +265,50 @@ impl Opcode {
 		}
 	}
 
+	/// Number of stack items consumed and produced by an external opcode,
+	/// or `None` if the opcode is not handled externally.
+	pub(crate) fn external_stack_io(&self) -> Option<(usize, usize)> {
+		Some(match *self {
+			Opcode::SHA3 => (2, 1),
+			Opcode::ADDRESS
+			| Opcode::SELFBALANCE
+			| Opcode::BASEFEE
+			| Opcode::BLOBBASEFEE
+			| Opcode::ORIGIN
+			| Opcode::CALLER
+			| Opcode::CALLVALUE
+			| Opcode::GASPRICE
+			| Opcode::RETURNDATASIZE
+			| Opcode::COINBASE
+			| Opcode::TIMESTAMP
+			| Opcode::NUMBER
+			| Opcode::DIFFICULTY
+			| Opcode::GASLIMIT
+			| Opcode::CHAINID
+			| Opcode::GAS => (0, 1),
+			Opcode::BALANCE
+			| Opcode::EXTCODESIZE
+			| Opcode::EXTCODEHASH
+			| Opcode::BLOCKHASH
+			| Opcode::BLOBHASH
+			| Opcode::SLOAD => (1, 1),
+			Opcode::EXTCODECOPY => (4, 0),
+			Opcode::RETURNDATACOPY => (3, 0),
+			Opcode::SSTORE => (2, 0),
+			Opcode::LOG0 => (2, 0),
+			Opcode::LOG1 => (3, 0),
+			Opcode::LOG2 => (4, 0),
+			Opcode::LOG3 => (5, 0),
+			Opcode::LOG4 => (6, 0),
+			Opcode::CREATE => (3, 1),
+			Opcode::CREATE2 => (4, 1),
+			Opcode::CALL | Opcode::CALLCODE => (7, 1),
+			Opcode::DELEGATECALL | Opcode::STATICCALL => (6, 1),
+			Opcode::SUICIDE => (1, 0),
+			_ => return None,
+		})
+	}
+
 	#[inline]
 	pub const fn as_u8(&self) -> u8 {
 		self.0
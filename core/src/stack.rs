@@ -49,6 +49,19 @@ impl Stack {
 		self.data.pop().ok_or(ExitError::StackUnderflow)
 	}
 
+	#[inline]
+	/// Pop the top `n` values from the stack, top first. If the stack holds
+	/// fewer than `n` values, returns the `StackUnderflow` error and leaves
+	/// the stack unchanged.
+	pub fn pop_n(&mut self, n: usize) -> Result<Vec<H256>, ExitError> {
+		if self.data.len() < n {
+			return Err(ExitError::StackUnderflow);
+		}
+		let mut values = self.data.split_off(self.data.len() - n);
+		values.reverse();
+		Ok(values)
+	}
+
 	#[inline]
 	/// Push a new value into the stack. If it will exceed the stack limit,
 	/// returns `StackOverflow` error and leaves the stack unchanged.
@@ -60,6 +73,19 @@ impl Stack {
 		Ok(())
 	}
 
+	#[inline]
+	/// Overwrite the value at the top of the stack in place. If the stack is
+	/// empty, returns the `StackUnderflow` error.
+	pub fn replace_top(&mut self, value: H256) -> Result<(), ExitError> {
+		match self.data.last_mut() {
+			Some(top) => {
+				*top = value;
+				Ok(())
+			}
+			None => Err(ExitError::StackUnderflow),
+		}
+	}
+
 	#[inline]
 	/// Peek a value at given index for the stack, where the top of
 	/// the stack is at index `0`. If the index is too large,
@@ -86,3 +112,59 @@ impl Stack {
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::Stack;
+	use crate::ExitError;
+	use primitive_types::H256;
+
+	#[test]
+	fn replace_top() {
+		let mut stack = Stack::new(1024);
+		assert_eq!(
+			stack.replace_top(H256::repeat_byte(0x01)),
+			Err(ExitError::StackUnderflow)
+		);
+
+		stack.push(H256::repeat_byte(0x01)).unwrap();
+		assert_eq!(stack.replace_top(H256::repeat_byte(0x02)), Ok(()));
+		assert_eq!(stack.len(), 1);
+		assert_eq!(stack.peek(0), Ok(H256::repeat_byte(0x02)));
+	}
+
+	#[test]
+	fn replace_top_keeps_depth() {
+		let mut stack = Stack::new(1024);
+		stack.push(H256::repeat_byte(0x01)).unwrap();
+		stack.push(H256::repeat_byte(0x02)).unwrap();
+
+		assert_eq!(stack.replace_top(H256::repeat_byte(0x03)), Ok(()));
+		assert_eq!(stack.len(), 2);
+		assert_eq!(stack.peek(0), Ok(H256::repeat_byte(0x03)));
+		assert_eq!(stack.peek(1), Ok(H256::repeat_byte(0x01)));
+	}
+
+	#[test]
+	fn pop_n() {
+		let mut stack = Stack::new(1024);
+		for byte in 1..=5 {
+			stack.push(H256::repeat_byte(byte)).unwrap();
+		}
+
+		assert_eq!(
+			stack.pop_n(3),
+			Ok(vec![
+				H256::repeat_byte(0x05),
+				H256::repeat_byte(0x04),
+				H256::repeat_byte(0x03),
+			])
+		);
+		assert_eq!(stack.len(), 2);
+		assert_eq!(stack.peek(0), Ok(H256::repeat_byte(0x02)));
+
+		assert_eq!(stack.pop_n(3), Err(ExitError::StackUnderflow));
+		assert_eq!(stack.len(), 2);
+		assert_eq!(stack.peek(0), Ok(H256::repeat_byte(0x02)));
+	}
+}
@@ -0,0 +1,23 @@
+//! Allows to listen to core events.
+
+environmental::environmental!(listener: dyn EventListener + 'static);
+
+pub trait EventListener {
+	fn event(&mut self, event: Event);
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum Event {
+	/// The effective memory length grew from `from` to `to` bytes.
+	MemoryGrow { from: usize, to: usize },
+}
+
+// Expose `listener::with` to the crate only.
+pub(crate) fn with<F: FnOnce(&mut (dyn EventListener + 'static))>(f: F) {
+	listener::with(f);
+}
+
+/// Run closure with provided listener.
+pub fn using<R, F: FnOnce() -> R>(new: &mut (dyn EventListener + 'static), f: F) -> R {
+	listener::using(new, f)
+}
@@ -0,0 +1,177 @@
+use crate::Opcode;
+
+/// Kind of a trapped opcode, telling the host how it has to be resolved.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TrapKind {
+	/// `SHA3`
+	Sha3,
+	/// `ADDRESS`
+	Address,
+	/// `BALANCE`
+	Balance,
+	/// `SELFBALANCE`
+	SelfBalance,
+	/// `ORIGIN`
+	Origin,
+	/// `CALLER`
+	Caller,
+	/// `CALLVALUE`
+	CallValue,
+	/// `GASPRICE`
+	GasPrice,
+	/// `EXTCODESIZE`
+	ExtCodeSize,
+	/// `EXTCODEHASH`
+	ExtCodeHash,
+	/// `EXTCODECOPY`
+	ExtCodeCopy,
+	/// `RETURNDATASIZE`
+	ReturnDataSize,
+	/// `RETURNDATACOPY`
+	ReturnDataCopy,
+	/// `BLOCKHASH`
+	BlockHash,
+	/// `COINBASE`
+	Coinbase,
+	/// `TIMESTAMP`
+	Timestamp,
+	/// `NUMBER`
+	Number,
+	/// `DIFFICULTY`
+	Difficulty,
+	/// `GASLIMIT`
+	GasLimit,
+	/// `SLOAD`
+	Sload,
+	/// `SSTORE`
+	Sstore,
+	/// `GAS`
+	Gas,
+	/// `LOGn`, with the number of topics.
+	Log(u8),
+	/// `SUICIDE`
+	Suicide,
+	/// `CREATE`
+	Create,
+	/// `CREATE2`
+	Create2,
+	/// `CALL`
+	Call,
+	/// `CALLCODE`
+	CallCode,
+	/// `DELEGATECALL`
+	DelegateCall,
+	/// `STATICCALL`
+	StaticCall,
+	/// `CHAINID`
+	ChainId,
+	/// `BASEFEE`
+	BaseFee,
+	/// `BLOBHASH`
+	BlobHash,
+	/// `BLOBBASEFEE`
+	BlobBaseFee,
+	/// Any other opcode, to be handled by the host directly.
+	Other(Opcode),
+}
+
+impl From<Opcode> for TrapKind {
+	fn from(opcode: Opcode) -> Self {
+		match opcode {
+			Opcode::SHA3 => Self::Sha3,
+			Opcode::ADDRESS => Self::Address,
+			Opcode::BALANCE => Self::Balance,
+			Opcode::SELFBALANCE => Self::SelfBalance,
+			Opcode::ORIGIN => Self::Origin,
+			Opcode::CALLER => Self::Caller,
+			Opcode::CALLVALUE => Self::CallValue,
+			Opcode::GASPRICE => Self::GasPrice,
+			Opcode::EXTCODESIZE => Self::ExtCodeSize,
+			Opcode::EXTCODEHASH => Self::ExtCodeHash,
+			Opcode::EXTCODECOPY => Self::ExtCodeCopy,
+			Opcode::RETURNDATASIZE => Self::ReturnDataSize,
+			Opcode::RETURNDATACOPY => Self::ReturnDataCopy,
+			Opcode::BLOCKHASH => Self::BlockHash,
+			Opcode::COINBASE => Self::Coinbase,
+			Opcode::TIMESTAMP => Self::Timestamp,
+			Opcode::NUMBER => Self::Number,
+			Opcode::DIFFICULTY => Self::Difficulty,
+			Opcode::GASLIMIT => Self::GasLimit,
+			Opcode::SLOAD => Self::Sload,
+			Opcode::SSTORE => Self::Sstore,
+			Opcode::GAS => Self::Gas,
+			Opcode::LOG0 => Self::Log(0),
+			Opcode::LOG1 => Self::Log(1),
+			Opcode::LOG2 => Self::Log(2),
+			Opcode::LOG3 => Self::Log(3),
+			Opcode::LOG4 => Self::Log(4),
+			Opcode::SUICIDE => Self::Suicide,
+			Opcode::CREATE => Self::Create,
+			Opcode::CREATE2 => Self::Create2,
+			Opcode::CALL => Self::Call,
+			Opcode::CALLCODE => Self::CallCode,
+			Opcode::DELEGATECALL => Self::DelegateCall,
+			Opcode::STATICCALL => Self::StaticCall,
+			Opcode::CHAINID => Self::ChainId,
+			Opcode::BASEFEE => Self::BaseFee,
+			Opcode::BLOBHASH => Self::BlobHash,
+			Opcode::BLOBBASEFEE => Self::BlobBaseFee,
+			_ => Self::Other(opcode),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::TrapKind;
+	use crate::Opcode;
+
+	#[test]
+	fn trap_kind_from_opcode() {
+		let cases = [
+			(Opcode::SHA3, TrapKind::Sha3),
+			(Opcode::ADDRESS, TrapKind::Address),
+			(Opcode::BALANCE, TrapKind::Balance),
+			(Opcode::SELFBALANCE, TrapKind::SelfBalance),
+			(Opcode::ORIGIN, TrapKind::Origin),
+			(Opcode::CALLER, TrapKind::Caller),
+			(Opcode::CALLVALUE, TrapKind::CallValue),
+			(Opcode::GASPRICE, TrapKind::GasPrice),
+			(Opcode::EXTCODESIZE, TrapKind::ExtCodeSize),
+			(Opcode::EXTCODEHASH, TrapKind::ExtCodeHash),
+			(Opcode::EXTCODECOPY, TrapKind::ExtCodeCopy),
+			(Opcode::RETURNDATASIZE, TrapKind::ReturnDataSize),
+			(Opcode::RETURNDATACOPY, TrapKind::ReturnDataCopy),
+			(Opcode::BLOCKHASH, TrapKind::BlockHash),
+			(Opcode::COINBASE, TrapKind::Coinbase),
+			(Opcode::TIMESTAMP, TrapKind::Timestamp),
+			(Opcode::NUMBER, TrapKind::Number),
+			(Opcode::DIFFICULTY, TrapKind::Difficulty),
+			(Opcode::GASLIMIT, TrapKind::GasLimit),
+			(Opcode::SLOAD, TrapKind::Sload),
+			(Opcode::SSTORE, TrapKind::Sstore),
+			(Opcode::GAS, TrapKind::Gas),
+			(Opcode::LOG0, TrapKind::Log(0)),
+			(Opcode::LOG1, TrapKind::Log(1)),
+			(Opcode::LOG2, TrapKind::Log(2)),
+			(Opcode::LOG3, TrapKind::Log(3)),
+			(Opcode::LOG4, TrapKind::Log(4)),
+			(Opcode::SUICIDE, TrapKind::Suicide),
+			(Opcode::CREATE, TrapKind::Create),
+			(Opcode::CREATE2, TrapKind::Create2),
+			(Opcode::CALL, TrapKind::Call),
+			(Opcode::CALLCODE, TrapKind::CallCode),
+			(Opcode::DELEGATECALL, TrapKind::DelegateCall),
+			(Opcode::STATICCALL, TrapKind::StaticCall),
+			(Opcode::CHAINID, TrapKind::ChainId),
+			(Opcode::BASEFEE, TrapKind::BaseFee),
+			(Opcode::BLOBHASH, TrapKind::BlobHash),
+			(Opcode::BLOBBASEFEE, TrapKind::BlobBaseFee),
+			(Opcode(0x0c), TrapKind::Other(Opcode(0x0c))),
+		];
+
+		for (opcode, kind) in cases {
+			assert_eq!(TrapKind::from(opcode), kind);
+		}
+	}
+}
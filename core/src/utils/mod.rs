@@ -1,7 +1,61 @@
+pub mod rlp;
+
+use crate::Opcode;
+use alloc::vec::Vec;
 use core::cmp::Ordering;
 use core::ops::{Div, Rem};
 use primitive_types::U256;
 
+/// Big-endian encoding of a `U256` as 32 bytes.
+#[inline]
+pub fn u256_to_be_bytes(value: U256) -> [u8; 32] {
+	let mut bytes = [0u8; 32];
+	value.to_big_endian(&mut bytes);
+	bytes
+}
+
+/// Minimal big-endian encoding of a `U256`, without leading zero bytes. Zero
+/// encodes as an empty vector.
+pub fn u256_to_minimal_be_bytes(value: U256) -> Vec<u8> {
+	let bytes = u256_to_be_bytes(value);
+	let skip = (value.leading_zeros() / 8) as usize;
+	bytes[skip..].to_vec()
+}
+
+/// Decode a `U256` from its 32-byte big-endian encoding.
+#[inline]
+pub fn be_bytes_to_u256(bytes: &[u8; 32]) -> U256 {
+	U256::from_big_endian(bytes)
+}
+
+/// Whether the last instruction of `code`, walked respecting `PUSH`
+/// immediates, is a terminator (`STOP`, `JUMP`, `RETURN`, `REVERT`,
+/// `INVALID` or `SUICIDE`). Code ending inside `PUSH` data, or empty code,
+/// can run past its end.
+pub fn has_explicit_terminator(code: &[u8]) -> bool {
+	let mut last = None;
+	let mut i = 0;
+	while i < code.len() {
+		let opcode = Opcode(code[i]);
+		last = Some(opcode);
+		i += opcode.is_push().map_or(0, |n| n as usize) + 1;
+	}
+
+	if i > code.len() {
+		return false;
+	}
+
+	matches!(
+		last,
+		Some(Opcode::STOP)
+			| Some(Opcode::JUMP)
+			| Some(Opcode::RETURN)
+			| Some(Opcode::REVERT)
+			| Some(Opcode::INVALID)
+			| Some(Opcode::SUICIDE)
+	)
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub enum Sign {
 	Plus,
@@ -131,7 +185,10 @@ impl Rem for I256 {
 
 #[cfg(test)]
 mod tests {
-	use crate::utils::{Sign, I256};
+	use crate::utils::{
+		be_bytes_to_u256, has_explicit_terminator, u256_to_be_bytes, u256_to_minimal_be_bytes,
+		Sign, I256,
+	};
 	use primitive_types::U256;
 	use std::num::Wrapping;
 
@@ -165,4 +222,49 @@ mod tests {
 		assert_eq!(one_hundred / minus_one, neg_one_hundred);
 		assert_eq!(one_hundred / two, fifty);
 	}
+
+	#[test]
+	fn u256_be_bytes_round_trip() {
+		assert_eq!(u256_to_be_bytes(U256::zero()), [0u8; 32]);
+		assert_eq!(u256_to_be_bytes(U256::MAX), [0xffu8; 32]);
+
+		let mut one = [0u8; 32];
+		one[31] = 1;
+		assert_eq!(u256_to_be_bytes(U256::one()), one);
+
+		for value in [
+			U256::zero(),
+			U256::one(),
+			U256::from(u64::MAX) + 1,
+			U256::MAX,
+		] {
+			assert_eq!(be_bytes_to_u256(&u256_to_be_bytes(value)), value);
+		}
+	}
+
+	#[test]
+	fn explicit_terminator() {
+		// PUSH1 1, PUSH1 2, ADD, STOP
+		assert!(has_explicit_terminator(&[
+			0x60, 0x01, 0x60, 0x02, 0x01, 0x00
+		]));
+		// PUSH1 1, PUSH1 2, ADD
+		assert!(!has_explicit_terminator(&[0x60, 0x01, 0x60, 0x02, 0x01]));
+		// PUSH2 0x00 with one byte of data missing
+		assert!(!has_explicit_terminator(&[0x61, 0x00]));
+		// PUSH1 0x00 whose data byte is STOP
+		assert!(!has_explicit_terminator(&[0x60, 0x00]));
+		assert!(!has_explicit_terminator(&[]));
+	}
+
+	#[test]
+	fn minimal_be_bytes() {
+		assert!(u256_to_minimal_be_bytes(U256::zero()).is_empty());
+		assert_eq!(u256_to_minimal_be_bytes(U256::from(0xff)), vec![0xff]);
+		assert_eq!(
+			u256_to_minimal_be_bytes(U256::from(0x0100)),
+			vec![0x01, 0x00]
+		);
+		assert_eq!(u256_to_minimal_be_bytes(U256::MAX), vec![0xff; 32]);
+	}
 }
@@ -0,0 +1,68 @@
+//! Minimal RLP encoding, covering what address derivation needs.
+
+use alloc::vec::Vec;
+
+/// Encode a byte string. A single byte below `0x80` is its own encoding.
+pub fn encode_bytes(bytes: &[u8]) -> Vec<u8> {
+	if bytes.len() == 1 && bytes[0] < 0x80 {
+		return bytes.to_vec();
+	}
+
+	let mut out = Vec::with_capacity(bytes.len() + 9);
+	push_length(&mut out, bytes.len(), 0x80);
+	out.extend_from_slice(bytes);
+	out
+}
+
+/// Encode a list of already encoded items.
+pub fn encode_list(items: &[Vec<u8>]) -> Vec<u8> {
+	let payload_len = items.iter().map(Vec::len).sum();
+	let mut out = Vec::with_capacity(payload_len + 9);
+	push_length(&mut out, payload_len, 0xc0);
+	for item in items {
+		out.extend_from_slice(item);
+	}
+	out
+}
+
+/// Push the prefix of a payload of `len` bytes. Payloads up to 55 bytes add
+/// their length to `offset`; longer ones are followed by their big-endian
+/// length.
+fn push_length(out: &mut Vec<u8>, len: usize, offset: u8) {
+	if len <= 55 {
+		out.push(offset + len as u8);
+		return;
+	}
+
+	let len_bytes = (len as u64).to_be_bytes();
+	let skip = len_bytes.iter().take_while(|b| **b == 0).count();
+	out.push(offset + 55 + (len_bytes.len() - skip) as u8);
+	out.extend_from_slice(&len_bytes[skip..]);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{encode_bytes, encode_list};
+
+	#[test]
+	fn known_vectors() {
+		assert_eq!(encode_list(&[]), vec![0xc0]);
+		assert_eq!(encode_bytes(b"dog"), vec![0x83, b'd', b'o', b'g']);
+		assert_eq!(encode_bytes(&[]), vec![0x80]);
+		assert_eq!(encode_bytes(&[0x0f]), vec![0x0f]);
+		assert_eq!(encode_bytes(&[0x80]), vec![0x81, 0x80]);
+
+		let long = [b'a'; 56];
+		assert_eq!(encode_bytes(&long)[..2], [0xb8, 56]);
+
+		// [address, nonce] as hashed by CREATE, for nonce 1.
+		let address = [0x11; 20];
+		let mut expected = vec![0xd6, 0x94];
+		expected.extend_from_slice(&address);
+		expected.push(0x01);
+		assert_eq!(
+			encode_list(&[encode_bytes(&address), encode_bytes(&[0x01])]),
+			expected
+		);
+	}
+}
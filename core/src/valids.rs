@@ -53,4 +53,37 @@ impl Valids {
 
 		true
 	}
+
+	/// All valid jump destinations, in ascending order.
+	pub fn jumpdests(&self) -> Vec<usize> {
+		self.0
+			.iter()
+			.enumerate()
+			.filter_map(|(position, valid)| if *valid { Some(position) } else { None })
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::Valids;
+
+	#[test]
+	fn push_immediate_is_not_jumpdest() {
+		// PUSH1 0x5B, JUMPDEST
+		let valids = Valids::new(&[0x60, 0x5b, 0x5b]);
+
+		assert!(!valids.is_valid(0));
+		assert!(!valids.is_valid(1));
+		assert!(valids.is_valid(2));
+		assert!(!valids.is_valid(3));
+	}
+
+	#[test]
+	fn jumpdests_skip_push_data() {
+		// JUMPDEST, PUSH1 0x5B, JUMPDEST, STOP
+		let valids = Valids::new(&[0x5b, 0x60, 0x5b, 0x5b, 0x00]);
+
+		assert_eq!(valids.jumpdests(), vec![0, 3]);
+	}
 }
@@ -6,7 +6,6 @@ pub const G_MID: u64 = 8;
 pub const G_HIGH: u64 = 10;
 pub const G_JUMPDEST: u64 = 1;
 pub const R_SUICIDE: i64 = 24000;
-pub const G_CREATE: u64 = 32000;
 pub const G_CALLVALUE: u64 = 9000;
 pub const G_NEWACCOUNT: u64 = 25000;
 pub const G_EXP: u64 = 10;
@@ -40,9 +40,9 @@ pub fn sstore_refund(original: H256, current: H256, new: H256, config: &Config)
 
 				if original == new {
 					if original == H256::default() {
-						refund += (config.gas_sstore_set - config.gas_sload) as i64;
+						refund += (config.gas_costs.sstore_set - config.gas_costs.sload) as i64;
 					} else {
-						refund += (config.gas_sstore_reset - config.gas_sload) as i64;
+						refund += (config.gas_costs.sstore_reset - config.gas_costs.sload) as i64;
 					}
 				}
 
@@ -58,8 +58,8 @@ pub fn sstore_refund(original: H256, current: H256, new: H256, config: &Config)
 	}
 }
 
-pub fn create2_cost(len: U256) -> Result<u64, ExitError> {
-	let base = U256::from(G_CREATE);
+pub fn create2_cost(len: U256, config: &Config) -> Result<u64, ExitError> {
+	let base = U256::from(config.gas_costs.create);
 	// ceil(len / 32.0)
 	let sha_addup_base = len / U256::from(32)
 		+ if len % U256::from(32) == U256::zero() {
@@ -85,7 +85,7 @@ pub fn exp_cost(power: U256, config: &Config) -> Result<u64, ExitError> {
 	} else {
 		let gas = U256::from(G_EXP)
 			.checked_add(
-				U256::from(config.gas_expbyte)
+				U256::from(config.gas_costs.expbyte)
 					.checked_mul(U256::from(crate::utils::log2floor(power) / 8 + 1))
 					.ok_or(ExitError::OutOfGas)?,
 			)
@@ -125,17 +125,21 @@ pub fn verylowcopy_cost(len: U256) -> Result<u64, ExitError> {
 pub fn extcodecopy_cost(len: U256, is_cold: bool, config: &Config) -> Result<u64, ExitError> {
 	let wordd = len / U256::from(32);
 	let wordr = len % U256::from(32);
-	let gas = U256::from(address_access_cost(is_cold, config.gas_ext_code, config))
-		.checked_add(
-			U256::from(G_COPY)
-				.checked_mul(if wordr == U256::zero() {
-					wordd
-				} else {
-					wordd + U256::one()
-				})
-				.ok_or(ExitError::OutOfGas)?,
-		)
-		.ok_or(ExitError::OutOfGas)?;
+	let gas = U256::from(address_access_cost(
+		is_cold,
+		config.gas_costs.ext_code,
+		config,
+	))
+	.checked_add(
+		U256::from(G_COPY)
+			.checked_mul(if wordr == U256::zero() {
+				wordd
+			} else {
+				wordd + U256::one()
+			})
+			.ok_or(ExitError::OutOfGas)?,
+	)
+	.ok_or(ExitError::OutOfGas)?;
 
 	if gas > U256::from(u64::MAX) {
 		return Err(ExitError::OutOfGas);
@@ -188,12 +192,12 @@ pub fn sha3_cost(len: U256) -> Result<u64, ExitError> {
 pub fn sload_cost(is_cold: bool, config: &Config) -> u64 {
 	if config.increase_state_access_gas {
 		if is_cold {
-			config.gas_sload_cold
+			config.gas_costs.sload_cold
 		} else {
-			config.gas_storage_read_warm
+			config.gas_costs.storage_read_warm
 		}
 	} else {
-		config.gas_sload
+		config.gas_costs.sload
 	}
 }
 
@@ -207,7 +211,7 @@ pub fn sstore_cost(
 	config: &Config,
 ) -> Result<u64, ExitError> {
 	let gas_cost = if config.estimate {
-		config.gas_sstore_set
+		config.gas_costs.sstore_set
 	} else {
 		if config.sstore_gas_metering {
 			if config.sstore_revert_under_stipend && gas <= config.call_stipend {
@@ -215,30 +219,30 @@ pub fn sstore_cost(
 			}
 
 			if new == current {
-				config.gas_sload
+				config.gas_costs.sload
 			} else {
 				if original == current {
 					if original == H256::zero() {
-						config.gas_sstore_set
+						config.gas_costs.sstore_set
 					} else {
-						config.gas_sstore_reset
+						config.gas_costs.sstore_reset
 					}
 				} else {
-					config.gas_sload
+					config.gas_costs.sload
 				}
 			}
 		} else {
 			if current == H256::zero() && new != H256::zero() {
-				config.gas_sstore_set
+				config.gas_costs.sstore_set
 			} else {
-				config.gas_sstore_reset
+				config.gas_costs.sstore_reset
 			}
 		}
 	};
 	Ok(
 		// In EIP-2929 we charge extra if the slot has not been used yet in this transaction
 		if is_cold {
-			gas_cost + config.gas_sload_cold
+			gas_cost + config.gas_costs.sload_cold
 		} else {
 			gas_cost
 		},
@@ -254,14 +258,14 @@ pub fn suicide_cost(value: U256, is_cold: bool, target_exists: bool, config: &Co
 	};
 
 	let suicide_gas_topup = if should_charge_topup {
-		config.gas_suicide_new_account
+		config.gas_costs.suicide_new_account
 	} else {
 		0
 	};
 
-	let mut gas = config.gas_suicide + suicide_gas_topup;
+	let mut gas = config.gas_costs.suicide + suicide_gas_topup;
 	if config.increase_state_access_gas && is_cold {
-		gas += config.gas_account_access_cold
+		gas += config.gas_costs.account_access_cold
 	}
 	gas
 }
@@ -275,7 +279,7 @@ pub fn call_cost(
 	config: &Config,
 ) -> u64 {
 	let transfers_value = value != U256::default();
-	address_access_cost(is_cold, config.gas_call, config)
+	address_access_cost(is_cold, config.gas_costs.call, config)
 		+ xfer_cost(is_call_or_callcode, transfers_value)
 		+ new_cost(is_call_or_staticcall, new_account, transfers_value, config)
 }
@@ -283,9 +287,9 @@ pub fn call_cost(
 pub fn address_access_cost(is_cold: bool, regular_value: u64, config: &Config) -> u64 {
 	if config.increase_state_access_gas {
 		if is_cold {
-			config.gas_account_access_cold
+			config.gas_costs.account_access_cold
 		} else {
-			config.gas_storage_read_warm
+			config.gas_costs.storage_read_warm
 		}
 	} else {
 		regular_value
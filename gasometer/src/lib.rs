@@ -28,7 +28,7 @@ mod memory;
 mod utils;
 
 use alloc::vec::Vec;
-use core::cmp::max;
+use core::cmp::{max, min};
 use evm_core::{ExitError, Opcode, Stack};
 use evm_runtime::{Config, Handler};
 use primitive_types::{H160, H256, U256};
@@ -72,6 +72,7 @@ impl<'config> Gasometer<'config> {
 				memory_gas: 0,
 				used_gas: 0,
 				refunded_gas: 0,
+				nominal_gas: 0,
 				config,
 			}),
 		}
@@ -124,6 +125,24 @@ impl<'config> Gasometer<'config> {
 		}
 	}
 
+	#[inline]
+	/// Gas that would have been charged while `Config::disable_gas_metering`
+	/// is set.
+	pub fn nominal_gas(&self) -> u64 {
+		match self.inner.as_ref() {
+			Ok(inner) => inner.nominal_gas,
+			Err(_) => 0,
+		}
+	}
+
+	#[inline]
+	/// Add to the nominal gas counter, without charging anything.
+	pub fn record_nominal_gas(&mut self, gas: u64) -> Result<(), ExitError> {
+		let inner = self.inner_mut()?;
+		inner.nominal_gas = inner.nominal_gas.saturating_add(gas);
+		Ok(())
+	}
+
 	/// Explicitly fail the gasometer with out of gas. Return `OutOfGas` error.
 	pub fn fail(&mut self) -> ExitError {
 		self.inner = Err(ExitError::OutOfGas);
@@ -131,15 +150,27 @@ impl<'config> Gasometer<'config> {
 	}
 
 	#[inline]
-	/// Record an explicit cost.
+	/// Record an explicit cost, scaled by `Config::gas_multiplier`.
 	pub fn record_cost(&mut self, cost: u64) -> Result<(), ExitError> {
+		let cost = cost.saturating_mul(self.config.gas_multiplier);
+		if self.config.disable_gas_metering {
+			return self.record_nominal_gas(cost);
+		}
+
+		self.record_unscaled_cost(cost)
+	}
+
+	#[inline]
+	/// Record an explicit cost as is, without applying `Config::gas_multiplier`.
+	/// Used for gas handed to a child call or create, which comes back
+	/// unscaled as a stipend.
+	pub fn record_unscaled_cost(&mut self, cost: u64) -> Result<(), ExitError> {
 		event!(RecordCost {
 			cost,
 			snapshot: self.snapshot(),
 		});
 
-		let all_gas_cost = self.total_used_gas() + cost;
-		if self.gas_limit < all_gas_cost {
+		if self.gas() < cost {
 			self.inner = Err(ExitError::OutOfGas);
 			return Err(ExitError::OutOfGas);
 		}
@@ -175,11 +206,27 @@ impl<'config> Gasometer<'config> {
 	) -> Result<(), ExitError> {
 		let gas = self.gas();
 
+		if self.config.disable_gas_metering {
+			let gas_multiplier = self.config.gas_multiplier;
+			let inner = self.inner_mut()?;
+			let memory_gas = match memory {
+				Some(memory) => inner.memory_gas(memory)?,
+				None => inner.memory_gas,
+			};
+			let gas_cost = inner
+				.gas_cost(cost, gas)?
+				.saturating_mul(gas_multiplier)
+				.saturating_add(memory_gas - inner.memory_gas);
+			inner.memory_gas = memory_gas;
+			return self.record_nominal_gas(gas_cost);
+		}
+
 		let memory_gas = match memory {
 			Some(memory) => try_or_fail!(self.inner, self.inner_mut()?.memory_gas(memory)),
 			None => self.inner_mut()?.memory_gas,
 		};
-		let gas_cost = try_or_fail!(self.inner, self.inner_mut()?.gas_cost(cost, gas));
+		let gas_cost = try_or_fail!(self.inner, self.inner_mut()?.gas_cost(cost, gas))
+			.saturating_mul(self.config.gas_multiplier);
 		let gas_refund = self.inner_mut()?.gas_refund(cost);
 		let used_gas = self.inner_mut()?.used_gas;
 
@@ -190,13 +237,17 @@ impl<'config> Gasometer<'config> {
 			snapshot: self.snapshot(),
 		});
 
-		let all_gas_cost = memory_gas + used_gas + gas_cost;
-		if self.gas_limit < all_gas_cost {
-			self.inner = Err(ExitError::OutOfGas);
-			return Err(ExitError::OutOfGas);
-		}
-
-		let after_gas = self.gas_limit - all_gas_cost;
+		let after_gas = match memory_gas
+			.checked_add(used_gas)
+			.and_then(|gas| gas.checked_add(gas_cost))
+			.and_then(|all_gas_cost| self.gas_limit.checked_sub(all_gas_cost))
+		{
+			Some(after_gas) => after_gas,
+			None => {
+				self.inner = Err(ExitError::OutOfGas);
+				return Err(ExitError::OutOfGas);
+			}
+		};
 		try_or_fail!(self.inner, self.inner_mut()?.extra_check(cost, after_gas));
 
 		self.inner_mut()?.used_gas += gas_cost;
@@ -220,32 +271,7 @@ impl<'config> Gasometer<'config> {
 
 	/// Record transaction cost.
 	pub fn record_transaction(&mut self, cost: TransactionCost) -> Result<(), ExitError> {
-		let gas_cost = match cost {
-			TransactionCost::Call {
-				zero_data_len,
-				non_zero_data_len,
-				access_list_address_len,
-				access_list_storage_len,
-			} => {
-				self.config.gas_transaction_call
-					+ zero_data_len as u64 * self.config.gas_transaction_zero_data
-					+ non_zero_data_len as u64 * self.config.gas_transaction_non_zero_data
-					+ access_list_address_len as u64 * self.config.gas_access_list_address
-					+ access_list_storage_len as u64 * self.config.gas_access_list_storage_key
-			}
-			TransactionCost::Create {
-				zero_data_len,
-				non_zero_data_len,
-				access_list_address_len,
-				access_list_storage_len,
-			} => {
-				self.config.gas_transaction_create
-					+ zero_data_len as u64 * self.config.gas_transaction_zero_data
-					+ non_zero_data_len as u64 * self.config.gas_transaction_non_zero_data
-					+ access_list_address_len as u64 * self.config.gas_access_list_address
-					+ access_list_storage_len as u64 * self.config.gas_access_list_storage_key
-			}
-		};
+		let gas_cost = cost.gas_cost(self.config);
 
 		event!(RecordTransaction {
 			cost: gas_cost,
@@ -302,6 +328,36 @@ pub fn create_transaction_cost(data: &[u8], access_list: &[(H160, Vec<H256>)]) -
 	}
 }
 
+/// Intrinsic gas of a transaction, to check up front that its gas limit
+/// covers it.
+pub fn intrinsic_gas(
+	data: &[u8],
+	is_create: bool,
+	access_list: &[(H160, Vec<H256>)],
+	config: &Config,
+) -> u64 {
+	let cost = if is_create {
+		create_transaction_cost(data, access_list)
+	} else {
+		call_transaction_cost(data, access_list)
+	};
+	cost.gas_cost(config)
+}
+
+/// Effective gas price of an EIP-1559 transaction, that is
+/// `min(max_fee, base_fee + max_priority_fee)`.
+pub fn effective_gas_price(base_fee: U256, max_fee: U256, max_priority_fee: U256) -> U256 {
+	min(max_fee, base_fee.saturating_add(max_priority_fee))
+}
+
+/// Check create init code of `len` bytes against `Config::max_initcode_size`.
+pub fn check_initcode_size(len: U256, config: &Config) -> Result<(), ExitError> {
+	match config.max_initcode_size {
+		Some(limit) if len > U256::from(limit) => Err(ExitError::InitcodeTooLarge),
+		_ => Ok(()),
+	}
+}
+
 /// Counts the number of addresses and storage keys in the access list
 fn count_access_list(access_list: &[(H160, Vec<H256>)]) -> (usize, usize) {
 	let access_list_address_len = access_list.len();
@@ -316,6 +372,7 @@ pub fn static_opcode_cost(opcode: Opcode) -> Option<u64> {
 		let mut table = [None; 256];
 
 		table[Opcode::STOP.as_usize()] = Some(consts::G_ZERO);
+		table[Opcode::INVALID.as_usize()] = Some(consts::G_ZERO);
 		table[Opcode::CALLDATASIZE.as_usize()] = Some(consts::G_BASE);
 		table[Opcode::CODESIZE.as_usize()] = Some(consts::G_BASE);
 		table[Opcode::POP.as_usize()] = Some(consts::G_BASE);
@@ -464,6 +521,10 @@ pub fn dynamic_opcode_cost<H: Handler>(
 		Opcode::BASEFEE if config.has_base_fee => GasCost::Base,
 		Opcode::BASEFEE => GasCost::Invalid(opcode),
 
+		Opcode::BLOBHASH if config.has_blob_opcodes => GasCost::VeryLow,
+		Opcode::BLOBBASEFEE if config.has_blob_opcodes => GasCost::Base,
+		Opcode::BLOBHASH | Opcode::BLOBBASEFEE => GasCost::Invalid(opcode),
+
 		Opcode::EXTCODESIZE => {
 			let target = stack.peek(0)?.into();
 			storage_target = StorageTarget::Address(target);
@@ -582,10 +643,15 @@ pub fn dynamic_opcode_cost<H: Handler>(
 			n: 4,
 			len: U256::from_big_endian(&stack.peek(1)?[..]),
 		},
-		Opcode::CREATE if !is_static => GasCost::Create,
-		Opcode::CREATE2 if !is_static && config.has_create2 => GasCost::Create2 {
-			len: U256::from_big_endian(&stack.peek(2)?[..]),
-		},
+		Opcode::CREATE if !is_static => {
+			check_initcode_size(U256::from_big_endian(&stack.peek(2)?[..]), config)?;
+			GasCost::Create
+		}
+		Opcode::CREATE2 if !is_static && config.has_create2 => {
+			let len = U256::from_big_endian(&stack.peek(2)?[..]);
+			check_initcode_size(len, config)?;
+			GasCost::Create2 { len }
+		}
 		Opcode::SUICIDE if !is_static => {
 			let target = stack.peek(0)?.into();
 			storage_target = StorageTarget::Address(target);
@@ -685,29 +751,20 @@ struct Inner<'config> {
 	memory_gas: u64,
 	used_gas: u64,
 	refunded_gas: i64,
+	nominal_gas: u64,
 	config: &'config Config,
 }
 
 impl<'config> Inner<'config> {
 	fn memory_gas(&self, memory: MemoryCost) -> Result<u64, ExitError> {
-		let from = memory.offset;
-		let len = memory.len;
-
-		if len == U256::zero() {
+		if memory.len == U256::zero() {
 			return Ok(self.memory_gas);
 		}
 
-		let end = from.checked_add(len).ok_or(ExitError::OutOfGas)?;
+		let new = memory.try_words()?;
+		let memory_gas = memory::memory_gas(new)?.saturating_mul(self.config.gas_multiplier);
 
-		if end > U256::from(usize::MAX) {
-			return Err(ExitError::OutOfGas);
-		}
-		let end = end.as_usize();
-
-		let rem = end % 32;
-		let new = if rem == 0 { end / 32 } else { end / 32 + 1 };
-
-		Ok(max(self.memory_gas, memory::memory_gas(new)?))
+		Ok(max(self.memory_gas, memory_gas))
 	}
 
 	fn extra_check(&self, cost: GasCost, after_gas: u64) -> Result<(), ExitError> {
@@ -793,8 +850,8 @@ impl<'config> Inner<'config> {
 			GasCost::Log { n, len } => costs::log_cost(n, len)?,
 			GasCost::VeryLowCopy { len } => costs::verylowcopy_cost(len)?,
 			GasCost::Exp { power } => costs::exp_cost(power, self.config)?,
-			GasCost::Create => consts::G_CREATE,
-			GasCost::Create2 { len } => costs::create2_cost(len)?,
+			GasCost::Create => self.config.gas_costs.create,
+			GasCost::Create2 { len } => costs::create2_cost(len, self.config)?,
 			GasCost::SLoad { target_is_cold } => costs::sload_cost(target_is_cold, self.config),
 
 			GasCost::Zero => consts::G_ZERO,
@@ -803,20 +860,24 @@ impl<'config> Inner<'config> {
 			GasCost::Low => consts::G_LOW,
 			GasCost::Invalid(opcode) => return Err(ExitError::InvalidCode(opcode)),
 
-			GasCost::ExtCodeSize { target_is_cold } => {
-				costs::address_access_cost(target_is_cold, self.config.gas_ext_code, self.config)
-			}
+			GasCost::ExtCodeSize { target_is_cold } => costs::address_access_cost(
+				target_is_cold,
+				self.config.gas_costs.ext_code,
+				self.config,
+			),
 			GasCost::ExtCodeCopy {
 				target_is_cold,
 				len,
 			} => costs::extcodecopy_cost(len, target_is_cold, self.config)?,
-			GasCost::Balance { target_is_cold } => {
-				costs::address_access_cost(target_is_cold, self.config.gas_balance, self.config)
-			}
+			GasCost::Balance { target_is_cold } => costs::address_access_cost(
+				target_is_cold,
+				self.config.gas_costs.balance,
+				self.config,
+			),
 			GasCost::BlockHash => consts::G_BLOCKHASH,
 			GasCost::ExtCodeHash { target_is_cold } => costs::address_access_cost(
 				target_is_cold,
-				self.config.gas_ext_code_hash,
+				self.config.gas_costs.ext_code_hash,
 				self.config,
 			),
 		})
@@ -1024,7 +1085,56 @@ pub enum TransactionCost {
 	},
 }
 
+impl TransactionCost {
+	/// Intrinsic gas of the transaction under the given config.
+	pub fn gas_cost(&self, config: &Config) -> u64 {
+		match *self {
+			TransactionCost::Call {
+				zero_data_len,
+				non_zero_data_len,
+				access_list_address_len,
+				access_list_storage_len,
+			} => {
+				config.gas_transaction_call
+					+ zero_data_len as u64 * config.gas_transaction_zero_data
+					+ non_zero_data_len as u64 * config.gas_transaction_non_zero_data
+					+ access_list_address_len as u64 * config.gas_access_list_address
+					+ access_list_storage_len as u64 * config.gas_access_list_storage_key
+			}
+			TransactionCost::Create {
+				zero_data_len,
+				non_zero_data_len,
+				access_list_address_len,
+				access_list_storage_len,
+			} => {
+				config.gas_transaction_create
+					+ zero_data_len as u64 * config.gas_transaction_zero_data
+					+ non_zero_data_len as u64 * config.gas_transaction_non_zero_data
+					+ access_list_address_len as u64 * config.gas_access_list_address
+					+ access_list_storage_len as u64 * config.gas_access_list_storage_key
+			}
+		}
+	}
+}
+
 impl MemoryCost {
+	/// Number of 32-byte words needed to cover `offset + len`, rounded up.
+	/// Returns `OutOfGas` if the end of the range does not fit in `u64`.
+	pub fn try_words(&self) -> Result<u64, ExitError> {
+		let end = self
+			.offset
+			.checked_add(self.len)
+			.ok_or(ExitError::OutOfGas)?;
+
+		if end > U256::from(u64::MAX) {
+			return Err(ExitError::OutOfGas);
+		}
+		let end = end.as_u64();
+
+		let rem = end % 32;
+		Ok(if rem == 0 { end / 32 } else { end / 32 + 1 })
+	}
+
 	/// Join two memory cost together.
 	pub fn join(self, other: MemoryCost) -> MemoryCost {
 		if self.len == U256::zero() {
@@ -1045,3 +1155,255 @@ impl MemoryCost {
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::{
+		call_transaction_cost, effective_gas_price, intrinsic_gas, GasCost, Gasometer, MemoryCost,
+		H256, U256,
+	};
+	use evm_core::ExitError;
+	use evm_runtime::{Config, GasCosts};
+
+	#[test]
+	fn memory_cost_try_words() {
+		let cost = |offset: U256, len: u64| MemoryCost {
+			offset,
+			len: U256::from(len),
+		};
+
+		assert_eq!(cost(U256::zero(), 0).try_words(), Ok(0));
+		assert_eq!(cost(U256::zero(), 1).try_words(), Ok(1));
+		assert_eq!(cost(U256::zero(), 32).try_words(), Ok(1));
+		assert_eq!(cost(U256::from(1), 32).try_words(), Ok(2));
+
+		// `offset + len` just under and exactly at `u64::MAX`
+		let max = U256::from(u64::MAX);
+		assert_eq!(cost(max - 33, 32).try_words(), Ok((u64::MAX - 1) / 32 + 1));
+		assert_eq!(cost(max - 32, 32).try_words(), Ok(u64::MAX / 32 + 1));
+
+		// `offset + len` just over `u64::MAX`
+		assert_eq!(cost(max - 31, 32).try_words(), Err(ExitError::OutOfGas));
+		assert_eq!(cost(max, 1).try_words(), Err(ExitError::OutOfGas));
+
+		// `offset + len` overflowing `U256`
+		assert_eq!(cost(U256::MAX, 1).try_words(), Err(ExitError::OutOfGas));
+	}
+
+	#[test]
+	fn effective_gas_price_eip1559() {
+		let base_fee = U256::from(100);
+
+		// Capped by the priority fee.
+		assert_eq!(
+			effective_gas_price(base_fee, U256::from(200), U256::from(10)),
+			U256::from(110)
+		);
+		// Capped by the max fee.
+		assert_eq!(
+			effective_gas_price(base_fee, U256::from(105), U256::from(10)),
+			U256::from(105)
+		);
+		// Both bounds equal.
+		assert_eq!(
+			effective_gas_price(base_fee, U256::from(110), U256::from(10)),
+			U256::from(110)
+		);
+	}
+
+	#[test]
+	fn sha3_dynamic_cost() {
+		let config = Config::istanbul();
+		let sha3_gas = |len: u64| {
+			let mut gasometer = Gasometer::new(u64::MAX, &config);
+			gasometer
+				.record_dynamic_cost(
+					GasCost::Sha3 {
+						len: U256::from(len),
+					},
+					Some(MemoryCost {
+						offset: U256::zero(),
+						len: U256::from(len),
+					}),
+				)
+				.unwrap();
+			gasometer.total_used_gas()
+		};
+
+		// 30 + 6 per word, plus 3 per word of memory expansion.
+		assert_eq!(sha3_gas(0), 30);
+		assert_eq!(sha3_gas(32), 30 + 6 + 3);
+		assert_eq!(sha3_gas(100), 30 + 6 * 4 + 3 * 4);
+	}
+
+	#[test]
+	fn sload_cost_by_gas_costs() {
+		let sload_gas = |config: Config, target_is_cold: bool| {
+			let mut gasometer = Gasometer::new(u64::MAX, &config);
+			gasometer
+				.record_dynamic_cost(GasCost::SLoad { target_is_cold }, None)
+				.unwrap();
+			gasometer.total_used_gas()
+		};
+
+		let istanbul = Config::istanbul();
+		assert_eq!(istanbul.gas_costs, GasCosts::istanbul());
+		assert_eq!(sload_gas(istanbul, false), 800);
+
+		let berlin = Config::berlin();
+		assert_eq!(berlin.gas_costs, GasCosts::berlin());
+		assert_eq!(sload_gas(berlin.clone(), false), 100);
+		assert_eq!(sload_gas(berlin, true), 2100);
+
+		// Overriding a single cost leaves the rest of the fork intact.
+		let repriced = Config {
+			gas_costs: GasCosts {
+				sload: 200,
+				..GasCosts::istanbul()
+			},
+			..Config::istanbul()
+		};
+		assert_eq!(sload_gas(repriced, false), 200);
+	}
+
+	#[test]
+	fn storage_access_costs_by_gas_costs() {
+		let dynamic_gas = |config: &Config, cost: GasCost| {
+			let mut gasometer = Gasometer::new(u64::MAX, config);
+			gasometer.record_dynamic_cost(cost, None).unwrap();
+			gasometer.total_used_gas()
+		};
+		// SSTORE of a fresh slot to the value it already holds.
+		let noop_sstore = |target_is_cold| GasCost::SStore {
+			original: H256::zero(),
+			current: H256::zero(),
+			new: H256::zero(),
+			target_is_cold,
+		};
+
+		let berlin = Config::berlin();
+		assert_eq!(
+			dynamic_gas(
+				&berlin,
+				GasCost::SLoad {
+					target_is_cold: true
+				}
+			),
+			2100
+		);
+		assert_eq!(dynamic_gas(&berlin, noop_sstore(true)), 100 + 2100);
+		assert_eq!(dynamic_gas(&berlin, noop_sstore(false)), 100);
+
+		// SSTORE overwriting the original value of a slot.
+		let reset_sstore = |target_is_cold| GasCost::SStore {
+			original: H256::repeat_byte(1),
+			current: H256::repeat_byte(1),
+			new: H256::repeat_byte(2),
+			target_is_cold,
+		};
+		assert_eq!(dynamic_gas(&berlin, reset_sstore(true)), 2900 + 2100);
+
+		let custom = Config {
+			gas_costs: GasCosts::with_access_costs(50, 3000),
+			..Config::berlin()
+		};
+		assert_eq!(
+			dynamic_gas(
+				&custom,
+				GasCost::SLoad {
+					target_is_cold: true
+				}
+			),
+			3000
+		);
+		assert_eq!(
+			dynamic_gas(
+				&custom,
+				GasCost::SLoad {
+					target_is_cold: false
+				}
+			),
+			50
+		);
+		assert_eq!(dynamic_gas(&custom, noop_sstore(true)), 50 + 3000);
+		assert_eq!(dynamic_gas(&custom, noop_sstore(false)), 50);
+		assert_eq!(dynamic_gas(&custom, reset_sstore(false)), 2000);
+		assert_eq!(dynamic_gas(&custom, reset_sstore(true)), 2000 + 3000);
+	}
+
+	#[test]
+	fn gas_multiplier_scales_execution_costs_only() {
+		let config = Config {
+			gas_multiplier: 2,
+			..Config::istanbul()
+		};
+		let mut gasometer = Gasometer::new(u64::MAX, &config);
+
+		gasometer
+			.record_transaction(call_transaction_cost(&[], &[]))
+			.unwrap();
+		assert_eq!(gasometer.total_used_gas(), 21000);
+
+		// SSTORE clearing a slot: the cost is scaled, the refund is not.
+		let clear = GasCost::SStore {
+			original: H256::repeat_byte(1),
+			current: H256::repeat_byte(1),
+			new: H256::zero(),
+			target_is_cold: false,
+		};
+		gasometer.record_dynamic_cost(clear, None).unwrap();
+		assert_eq!(gasometer.total_used_gas(), 21000 + 2 * 5000);
+		assert_eq!(gasometer.refunded_gas(), 15000);
+	}
+
+	#[test]
+	fn record_cost_over_limit() {
+		let config = Config::istanbul();
+
+		let mut gasometer = Gasometer::new(1_000, &config);
+		assert_eq!(gasometer.record_cost(1_001), Err(ExitError::OutOfGas));
+		assert_eq!(gasometer.gas(), 0);
+
+		// The sum of used gas and cost would overflow.
+		let mut gasometer = Gasometer::new(1_000, &config);
+		gasometer.record_cost(500).unwrap();
+		assert_eq!(gasometer.record_cost(u64::MAX), Err(ExitError::OutOfGas));
+		assert_eq!(gasometer.gas(), 0);
+		assert_eq!(gasometer.total_used_gas(), 1_000);
+	}
+
+	#[test]
+	fn sstore_sentry() {
+		let config = Config::istanbul();
+		let sstore = |gas_limit: u64| {
+			let mut gasometer = Gasometer::new(gas_limit, &config);
+			gasometer.record_dynamic_cost(
+				GasCost::SStore {
+					original: H256::zero(),
+					current: H256::zero(),
+					new: H256::zero(),
+					target_is_cold: false,
+				},
+				None,
+			)
+		};
+
+		assert_eq!(sstore(2300), Err(ExitError::OutOfGas));
+		assert_eq!(sstore(2301), Ok(()));
+	}
+
+	#[test]
+	fn intrinsic_gas_of_transactions() {
+		let config = Config::istanbul();
+
+		assert_eq!(intrinsic_gas(&[0; 10], false, &[], &config), 21000 + 10 * 4);
+		assert_eq!(
+			intrinsic_gas(&[0, 1, 0, 2, 3], false, &[], &config),
+			21000 + 2 * 4 + 3 * 16
+		);
+		assert_eq!(
+			intrinsic_gas(&[0, 1, 0, 2, 3], true, &[], &config),
+			53000 + 2 * 4 + 3 * 16
+		);
+	}
+}
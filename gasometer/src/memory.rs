@@ -1,8 +1,7 @@
 use crate::consts::*;
 use evm_core::ExitError;
 
-pub fn memory_gas(a: usize) -> Result<u64, ExitError> {
-	let a = a as u64;
+pub fn memory_gas(a: u64) -> Result<u64, ExitError> {
 	G_MEMORY
 		.checked_mul(a)
 		.ok_or(ExitError::OutOfGas)?
@@ -44,3 +44,15 @@ pub struct Context {
 	/// Apparent value of the EVM.
 	pub apparent_value: U256,
 }
+
+impl Context {
+	/// Context of a top-level call transaction from `caller` to `address`
+	/// carrying `value`.
+	pub fn top_level(caller: H160, address: H160, value: U256) -> Self {
+		Self {
+			address,
+			caller,
+			apparent_value: value,
+		}
+	}
+}
@@ -22,7 +22,7 @@ macro_rules! pop_u256 {
 	( $machine:expr, $( $x:ident ),* ) => (
 		$(
 			let $x = match $machine.machine.stack_mut().pop() {
-				Ok(value) => U256::from_big_endian(&value[..]),
+				Ok(value) => crate::be_bytes_to_u256(&value.0),
 				Err(e) => return Control::Exit(e.into()),
 			};
 		)*
@@ -43,8 +43,7 @@ macro_rules! push {
 macro_rules! push_u256 {
 	( $machine:expr, $( $x:expr ),* ) => (
 		$(
-			let mut value = H256::default();
-			$x.to_big_endian(&mut value[..]);
+			let value = H256(crate::u256_to_be_bytes($x));
 			match $machine.machine.stack_mut().push(value) {
 				Ok(()) => (),
 				Err(e) => return Control::Exit(e.into()),
@@ -2,7 +2,7 @@
 mod macros;
 mod system;
 
-use crate::{CallScheme, ExitReason, Handler, Opcode, Runtime};
+use crate::{CallScheme, ExitReason, Handler, Opcode, Runtime, TrapKind};
 
 pub enum Control<H: Handler> {
 	Continue,
@@ -19,43 +19,41 @@ fn handle_other<H: Handler>(state: &mut Runtime, opcode: Opcode, handler: &mut H
 }
 
 pub fn eval<H: Handler>(state: &mut Runtime, opcode: Opcode, handler: &mut H) -> Control<H> {
-	match opcode {
-		Opcode::SHA3 => system::sha3(state),
-		Opcode::ADDRESS => system::address(state),
-		Opcode::BALANCE => system::balance(state, handler),
-		Opcode::SELFBALANCE => system::selfbalance(state, handler),
-		Opcode::ORIGIN => system::origin(state, handler),
-		Opcode::CALLER => system::caller(state),
-		Opcode::CALLVALUE => system::callvalue(state),
-		Opcode::GASPRICE => system::gasprice(state, handler),
-		Opcode::EXTCODESIZE => system::extcodesize(state, handler),
-		Opcode::EXTCODEHASH => system::extcodehash(state, handler),
-		Opcode::EXTCODECOPY => system::extcodecopy(state, handler),
-		Opcode::RETURNDATASIZE => system::returndatasize(state),
-		Opcode::RETURNDATACOPY => system::returndatacopy(state),
-		Opcode::BLOCKHASH => system::blockhash(state, handler),
-		Opcode::COINBASE => system::coinbase(state, handler),
-		Opcode::TIMESTAMP => system::timestamp(state, handler),
-		Opcode::NUMBER => system::number(state, handler),
-		Opcode::DIFFICULTY => system::difficulty(state, handler),
-		Opcode::GASLIMIT => system::gaslimit(state, handler),
-		Opcode::SLOAD => system::sload(state, handler),
-		Opcode::SSTORE => system::sstore(state, handler),
-		Opcode::GAS => system::gas(state, handler),
-		Opcode::LOG0 => system::log(state, 0, handler),
-		Opcode::LOG1 => system::log(state, 1, handler),
-		Opcode::LOG2 => system::log(state, 2, handler),
-		Opcode::LOG3 => system::log(state, 3, handler),
-		Opcode::LOG4 => system::log(state, 4, handler),
-		Opcode::SUICIDE => system::suicide(state, handler),
-		Opcode::CREATE => system::create(state, false, handler),
-		Opcode::CREATE2 => system::create(state, true, handler),
-		Opcode::CALL => system::call(state, CallScheme::Call, handler),
-		Opcode::CALLCODE => system::call(state, CallScheme::CallCode, handler),
-		Opcode::DELEGATECALL => system::call(state, CallScheme::DelegateCall, handler),
-		Opcode::STATICCALL => system::call(state, CallScheme::StaticCall, handler),
-		Opcode::CHAINID => system::chainid(state, handler),
-		Opcode::BASEFEE => system::base_fee(state, handler),
-		_ => handle_other(state, opcode, handler),
+	match TrapKind::from(opcode) {
+		TrapKind::Sha3 => system::sha3(state),
+		TrapKind::Address => system::address(state),
+		TrapKind::Balance => system::balance(state, handler),
+		TrapKind::SelfBalance => system::selfbalance(state, handler),
+		TrapKind::Origin => system::origin(state, handler),
+		TrapKind::Caller => system::caller(state),
+		TrapKind::CallValue => system::callvalue(state),
+		TrapKind::GasPrice => system::gasprice(state, handler),
+		TrapKind::ExtCodeSize => system::extcodesize(state, handler),
+		TrapKind::ExtCodeHash => system::extcodehash(state, handler),
+		TrapKind::ExtCodeCopy => system::extcodecopy(state, handler),
+		TrapKind::ReturnDataSize => system::returndatasize(state),
+		TrapKind::ReturnDataCopy => system::returndatacopy(state),
+		TrapKind::BlockHash => system::blockhash(state, handler),
+		TrapKind::Coinbase => system::coinbase(state, handler),
+		TrapKind::Timestamp => system::timestamp(state, handler),
+		TrapKind::Number => system::number(state, handler),
+		TrapKind::Difficulty => system::difficulty(state, handler),
+		TrapKind::GasLimit => system::gaslimit(state, handler),
+		TrapKind::Sload => system::sload(state, handler),
+		TrapKind::Sstore => system::sstore(state, handler),
+		TrapKind::Gas => system::gas(state, handler),
+		TrapKind::Log(n) => system::log(state, n, handler),
+		TrapKind::Suicide => system::suicide(state, handler),
+		TrapKind::Create => system::create(state, false, handler),
+		TrapKind::Create2 => system::create(state, true, handler),
+		TrapKind::Call => system::call(state, CallScheme::Call, handler),
+		TrapKind::CallCode => system::call(state, CallScheme::CallCode, handler),
+		TrapKind::DelegateCall => system::call(state, CallScheme::DelegateCall, handler),
+		TrapKind::StaticCall => system::call(state, CallScheme::StaticCall, handler),
+		TrapKind::ChainId => system::chainid(state, handler),
+		TrapKind::BaseFee => system::base_fee(state, handler),
+		TrapKind::BlobHash => system::blob_hash(state, handler),
+		TrapKind::BlobBaseFee => system::blob_base_fee(state, handler),
+		TrapKind::Other(opcode) => handle_other(state, opcode, handler),
 	}
 }
@@ -1,7 +1,7 @@
 use super::Control;
 use crate::{
-	CallScheme, Capture, Context, CreateScheme, ExitError, ExitFatal, ExitReason, ExitSucceed,
-	Handler, Runtime, Transfer,
+	u256_to_be_bytes, CallScheme, Capture, Context, CreateScheme, ExitError, ExitFatal, ExitReason,
+	ExitSucceed, Handler, Runtime, Transfer,
 };
 use alloc::vec::Vec;
 use core::cmp::min;
@@ -68,25 +68,38 @@ pub fn caller<H: Handler>(runtime: &mut Runtime) -> Control<H> {
 }
 
 pub fn callvalue<H: Handler>(runtime: &mut Runtime) -> Control<H> {
-	let mut ret = H256::default();
-	runtime.context.apparent_value.to_big_endian(&mut ret[..]);
-	push!(runtime, ret);
+	push!(
+		runtime,
+		H256(u256_to_be_bytes(runtime.context.apparent_value))
+	);
 
 	Control::Continue
 }
 
 pub fn gasprice<H: Handler>(runtime: &mut Runtime, handler: &H) -> Control<H> {
-	let mut ret = H256::default();
-	handler.gas_price().to_big_endian(&mut ret[..]);
-	push!(runtime, ret);
+	push!(runtime, H256(u256_to_be_bytes(handler.gas_price())));
 
 	Control::Continue
 }
 
 pub fn base_fee<H: Handler>(runtime: &mut Runtime, handler: &H) -> Control<H> {
-	let mut ret = H256::default();
-	handler.block_base_fee_per_gas().to_big_endian(&mut ret[..]);
-	push!(runtime, ret);
+	push!(
+		runtime,
+		H256(u256_to_be_bytes(handler.block_base_fee_per_gas()))
+	);
+
+	Control::Continue
+}
+
+pub fn blob_hash<H: Handler>(runtime: &mut Runtime, handler: &H) -> Control<H> {
+	pop_u256!(runtime, index);
+	push!(runtime, handler.blob_hash(index));
+
+	Control::Continue
+}
+
+pub fn blob_base_fee<H: Handler>(runtime: &mut Runtime, handler: &H) -> Control<H> {
+	push!(runtime, H256(u256_to_be_bytes(handler.blob_base_fee())));
 
 	Control::Continue
 }
@@ -182,7 +195,11 @@ pub fn number<H: Handler>(runtime: &mut Runtime, handler: &H) -> Control<H> {
 }
 
 pub fn difficulty<H: Handler>(runtime: &mut Runtime, handler: &H) -> Control<H> {
-	push_u256!(runtime, handler.block_difficulty());
+	if runtime.config.has_prevrandao {
+		push!(runtime, handler.block_randomness().unwrap_or_default());
+	} else {
+		push_u256!(runtime, handler.block_difficulty());
+	}
 	Control::Continue
 }
 
@@ -329,6 +346,16 @@ pub fn create<H: Handler>(runtime: &mut Runtime, is_create2: bool, handler: &mut
 pub fn call<H: Handler>(runtime: &mut Runtime, scheme: CallScheme, handler: &mut H) -> Control<H> {
 	runtime.return_data_buffer = Vec::new();
 
+	// Check the stack depth up front so an underflow never leaves the
+	// stack partially popped.
+	let arguments = match scheme {
+		CallScheme::Call | CallScheme::CallCode => 7,
+		CallScheme::DelegateCall | CallScheme::StaticCall => 6,
+	};
+	if runtime.machine.stack().len() < arguments {
+		return Control::Exit(ExitError::StackUnderflow.into());
+	}
+
 	pop_u256!(runtime, gas);
 	pop!(runtime, to);
 	let gas = if gas > U256::from(u64::MAX) {
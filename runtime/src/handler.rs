@@ -54,10 +54,30 @@ pub trait Handler {
 	fn block_timestamp(&self) -> U256;
 	/// Get environmental block difficulty.
 	fn block_difficulty(&self) -> U256;
+	/// Get environmental block randomness, replacing the difficulty after the
+	/// merge.
+	///
+	/// Defaults to `None`.
+	fn block_randomness(&self) -> Option<H256> {
+		None
+	}
 	/// Get environmental gas limit.
 	fn block_gas_limit(&self) -> U256;
 	/// Environmental block base fee.
 	fn block_base_fee_per_gas(&self) -> U256;
+	/// Get environmental blob base fee. See [EIP-7516](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-7516.md)
+	///
+	/// Defaults to zero.
+	fn blob_base_fee(&self) -> U256 {
+		U256::zero()
+	}
+	/// Get the versioned hash of the transaction blob at `index`, or zero if
+	/// out of range. See [EIP-4844](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-4844.md)
+	///
+	/// Defaults to zero for every index.
+	fn blob_hash(&self, _index: U256) -> H256 {
+		H256::zero()
+	}
 	/// Get environmental chain ID.
 	fn chain_id(&self) -> U256;
 
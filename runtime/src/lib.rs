@@ -33,6 +33,7 @@ pub use crate::context::{CallScheme, Context, CreateScheme};
 pub use crate::handler::{Handler, Transfer};
 pub use crate::interrupt::{Resolve, ResolveCall, ResolveCreate};
 
+use alloc::collections::BTreeMap;
 use alloc::rc::Rc;
 use alloc::vec::Vec;
 
@@ -111,7 +112,7 @@ pub struct Runtime<'config> {
 	status: Result<(), ExitReason>,
 	return_data_buffer: Vec<u8>,
 	context: Context,
-	_config: &'config Config,
+	config: &'config Config,
 }
 
 impl<'config> Runtime<'config> {
@@ -127,7 +128,7 @@ impl<'config> Runtime<'config> {
 			status: Ok(()),
 			return_data_buffer: Vec::new(),
 			context,
-			_config: config,
+			config,
 		}
 	}
 
@@ -163,32 +164,12 @@ impl<'config> Runtime<'config> {
 /// Runtime configuration.
 #[derive(Clone, Debug)]
 pub struct Config {
-	/// Gas paid for extcode.
-	pub gas_ext_code: u64,
-	/// Gas paid for extcodehash.
-	pub gas_ext_code_hash: u64,
-	/// Gas paid for sstore set.
-	pub gas_sstore_set: u64,
-	/// Gas paid for sstore reset.
-	pub gas_sstore_reset: u64,
+	/// Per-opcode base and access gas costs.
+	pub gas_costs: GasCosts,
 	/// Gas paid for sstore refund.
 	pub refund_sstore_clears: i64,
 	/// EIP-3529
 	pub max_refund_quotient: u64,
-	/// Gas paid for BALANCE opcode.
-	pub gas_balance: u64,
-	/// Gas paid for SLOAD opcode.
-	pub gas_sload: u64,
-	/// Gas paid for cold SLOAD opcode.
-	pub gas_sload_cold: u64,
-	/// Gas paid for SUICIDE opcode.
-	pub gas_suicide: u64,
-	/// Gas paid for SUICIDE opcode when it hits a new account.
-	pub gas_suicide_new_account: u64,
-	/// Gas paid for CALL opcode.
-	pub gas_call: u64,
-	/// Gas paid for EXP opcode for every byte.
-	pub gas_expbyte: u64,
 	/// Gas paid for a contract creation transaction.
 	pub gas_transaction_create: u64,
 	/// Gas paid for a message call transaction.
@@ -201,13 +182,10 @@ pub struct Config {
 	pub gas_access_list_address: u64,
 	/// Gas paid per storage key in transaction access list (see EIP-2930).
 	pub gas_access_list_storage_key: u64,
-	/// Gas paid for accessing cold account.
-	pub gas_account_access_cold: u64,
-	/// Gas paid for accessing ready storage.
-	pub gas_storage_read_warm: u64,
 	/// EIP-1283.
 	pub sstore_gas_metering: bool,
-	/// EIP-1706.
+	/// EIP-1706, the EIP-2200 sentry: SSTORE fails with out of gas when no
+	/// more than the call stipend is left.
 	pub sstore_revert_under_stipend: bool,
 	/// EIP-2929
 	pub increase_state_access_gas: bool,
@@ -231,8 +209,23 @@ pub struct Config {
 	pub memory_limit: usize,
 	/// Call limit.
 	pub call_stack_limit: usize,
-	/// Create contract limit.
+	/// Create contract limit, in bytes of deployed code. `None` disables the
+	/// check. Presets from Istanbul on use `Some(0x6000)`, the 24576 bytes of
+	/// [EIP-170](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-170.md).
 	pub create_contract_limit: Option<usize>,
+	/// Maximum size of create init code, in bytes. `None` disables the check.
+	/// [EIP-3860](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-3860.md)
+	/// sets it to 49152, twice the deployed code limit; no preset here
+	/// enables it.
+	pub max_initcode_size: Option<usize>,
+	/// Maximum call data size accepted by a call.
+	pub max_call_data_size: Option<usize>,
+	/// Maximum return data size produced by a call, including the revert data
+	/// of calls and creates.
+	pub max_return_data_size: Option<usize>,
+	/// Maximum number of logs a transaction may emit. `None` disables the
+	/// check.
+	pub max_logs: Option<usize>,
 	/// Call stipend.
 	pub call_stipend: u64,
 	/// Has delegate call.
@@ -253,35 +246,63 @@ pub struct Config {
 	pub has_ext_code_hash: bool,
 	/// Has ext block fee. See [EIP-3198](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-3198.md)
 	pub has_base_fee: bool,
+	/// Whether `DIFFICULTY` is replaced by `PREVRANDAO`. See [EIP-4399](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-4399.md)
+	pub has_prevrandao: bool,
+	/// Has `BLOBHASH` and `BLOBBASEFEE`. See [EIP-4844](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-4844.md)
+	/// and [EIP-7516](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-7516.md)
+	pub has_blob_opcodes: bool,
+	/// Whether code reads follow an account-code delegation indicator
+	/// (`0xef0100 || address`). See [EIP-7702](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-7702.md)
+	pub eip_7702: bool,
+	/// Whether transactions from senders with deployed code are rejected.
+	/// See [EIP-3607](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-3607.md)
+	pub eip_3607: bool,
+	/// Whether an invalid jump consumes all remaining gas of the call frame.
+	/// When unset, the frame is reverted and its remaining gas is returned.
+	pub bad_jump_consumes_all_gas: bool,
+	/// Whether calls to precompiles may transfer value. When unset, such calls
+	/// fail without running the precompile.
+	pub precompiles_accept_value: bool,
+	/// Whether the chain ID is mixed into the `CREATE2` address preimage, as
+	/// `0xff ++ caller ++ chain_id ++ salt ++ keccak256(init_code)`, so the
+	/// same deployment lands at different addresses on different chains.
+	/// This is non-standard: with it set, `CREATE2` addresses no longer match
+	/// [EIP-1014](https://github.com/ethereum/EIPs/blob/master/EIPS/eip-1014.md)
+	/// or any Ethereum network. No preset enables it.
+	pub create2_includes_chain_id: bool,
+	/// Whether an account deleted by `SELFDESTRUCT` is seen with empty code
+	/// for the rest of the transaction. When unset, as on Ethereum, its code
+	/// stays visible and callable until the transaction ends.
+	pub hide_deleted_code: bool,
+	/// Whether `transact_call` and `transact_create` reject a caller whose
+	/// balance does not cover `gas_limit * gas_price + value` with
+	/// `ExitError::OutOfFund`, before charging any gas or bumping the nonce.
+	/// When unset, the embedder is expected to have checked this.
+	pub check_upfront_funds: bool,
 	/// Whether the gasometer is running in estimate mode.
 	pub estimate: bool,
+	/// Multiplier applied to execution gas costs: opcode, memory, code
+	/// deposit and other explicitly recorded costs. The intrinsic
+	/// transaction cost and gas refunds are not scaled.
+	pub gas_multiplier: u64,
+	/// Whether opcode and explicit costs are only counted as nominal gas
+	/// instead of being charged, so execution never runs out of gas.
+	pub disable_gas_metering: bool,
 }
 
 impl Config {
 	/// Frontier hard fork configuration.
 	pub const fn frontier() -> Config {
 		Config {
-			gas_ext_code: 20,
-			gas_ext_code_hash: 20,
-			gas_balance: 20,
-			gas_sload: 50,
-			gas_sload_cold: 0,
-			gas_sstore_set: 20000,
-			gas_sstore_reset: 5000,
+			gas_costs: GasCosts::frontier(),
 			refund_sstore_clears: 15000,
 			max_refund_quotient: 2,
-			gas_suicide: 0,
-			gas_suicide_new_account: 0,
-			gas_call: 40,
-			gas_expbyte: 10,
 			gas_transaction_create: 21000,
 			gas_transaction_call: 21000,
 			gas_transaction_zero_data: 4,
 			gas_transaction_non_zero_data: 68,
 			gas_access_list_address: 0,
 			gas_access_list_storage_key: 0,
-			gas_account_access_cold: 0,
-			gas_storage_read_warm: 0,
 			sstore_gas_metering: false,
 			sstore_revert_under_stipend: false,
 			increase_state_access_gas: false,
@@ -295,6 +316,10 @@ impl Config {
 			memory_limit: usize::MAX,
 			call_stack_limit: 1024,
 			create_contract_limit: None,
+			max_initcode_size: None,
+			max_call_data_size: None,
+			max_return_data_size: None,
+			max_logs: None,
 			call_stipend: 2300,
 			has_delegate_call: false,
 			has_create2: false,
@@ -305,34 +330,33 @@ impl Config {
 			has_self_balance: false,
 			has_ext_code_hash: false,
 			has_base_fee: false,
+			has_prevrandao: false,
+			has_blob_opcodes: false,
+			eip_7702: false,
+			eip_3607: false,
+			bad_jump_consumes_all_gas: true,
+			precompiles_accept_value: true,
+			create2_includes_chain_id: false,
+			hide_deleted_code: false,
+			check_upfront_funds: false,
 			estimate: false,
+			gas_multiplier: 1,
+			disable_gas_metering: false,
 		}
 	}
 
 	/// Istanbul hard fork configuration.
 	pub const fn istanbul() -> Config {
 		Config {
-			gas_ext_code: 700,
-			gas_ext_code_hash: 700,
-			gas_balance: 700,
-			gas_sload: 800,
-			gas_sload_cold: 0,
-			gas_sstore_set: 20000,
-			gas_sstore_reset: 5000,
+			gas_costs: GasCosts::istanbul(),
 			refund_sstore_clears: 15000,
 			max_refund_quotient: 2,
-			gas_suicide: 5000,
-			gas_suicide_new_account: 25000,
-			gas_call: 700,
-			gas_expbyte: 50,
 			gas_transaction_create: 53000,
 			gas_transaction_call: 21000,
 			gas_transaction_zero_data: 4,
 			gas_transaction_non_zero_data: 16,
 			gas_access_list_address: 0,
 			gas_access_list_storage_key: 0,
-			gas_account_access_cold: 0,
-			gas_storage_read_warm: 0,
 			sstore_gas_metering: true,
 			sstore_revert_under_stipend: true,
 			increase_state_access_gas: false,
@@ -346,6 +370,10 @@ impl Config {
 			memory_limit: usize::MAX,
 			call_stack_limit: 1024,
 			create_contract_limit: Some(0x6000),
+			max_initcode_size: None,
+			max_call_data_size: None,
+			max_return_data_size: None,
+			max_logs: None,
 			call_stipend: 2300,
 			has_delegate_call: true,
 			has_create2: true,
@@ -356,7 +384,18 @@ impl Config {
 			has_self_balance: true,
 			has_ext_code_hash: true,
 			has_base_fee: false,
+			has_prevrandao: false,
+			has_blob_opcodes: false,
+			eip_7702: false,
+			eip_3607: false,
+			bad_jump_consumes_all_gas: true,
+			precompiles_accept_value: true,
+			create2_includes_chain_id: false,
+			hide_deleted_code: false,
+			check_upfront_funds: false,
 			estimate: false,
+			gas_multiplier: 1,
+			disable_gas_metering: false,
 		}
 	}
 
@@ -370,6 +409,47 @@ impl Config {
 		Self::config_with_derived_values(DerivedConfigInputs::london())
 	}
 
+	/// The Merge (Paris) hard fork configuration.
+	pub const fn merge() -> Config {
+		Self::config_with_derived_values(DerivedConfigInputs::merge())
+	}
+
+	/// The gas parameters of this configuration by name, for asserting a fork
+	/// preset against golden values.
+	pub fn gas_schedule_snapshot(&self) -> BTreeMap<&'static str, u64> {
+		let costs = &self.gas_costs;
+		[
+			("ext_code", costs.ext_code),
+			("ext_code_hash", costs.ext_code_hash),
+			("sstore_set", costs.sstore_set),
+			("sstore_reset", costs.sstore_reset),
+			("balance", costs.balance),
+			("sload", costs.sload),
+			("sload_cold", costs.sload_cold),
+			("suicide", costs.suicide),
+			("suicide_new_account", costs.suicide_new_account),
+			("call", costs.call),
+			("expbyte", costs.expbyte),
+			("account_access_cold", costs.account_access_cold),
+			("storage_read_warm", costs.storage_read_warm),
+			("create", costs.create),
+			("max_refund_quotient", self.max_refund_quotient),
+			("transaction_create", self.gas_transaction_create),
+			("transaction_call", self.gas_transaction_call),
+			("transaction_zero_data", self.gas_transaction_zero_data),
+			(
+				"transaction_non_zero_data",
+				self.gas_transaction_non_zero_data,
+			),
+			("access_list_address", self.gas_access_list_address),
+			("access_list_storage_key", self.gas_access_list_storage_key),
+			("call_stipend", self.call_stipend),
+		]
+		.iter()
+		.copied()
+		.collect()
+	}
+
 	const fn config_with_derived_values(inputs: DerivedConfigInputs) -> Config {
 		let DerivedConfigInputs {
 			gas_storage_read_warm,
@@ -377,43 +457,30 @@ impl Config {
 			gas_access_list_storage_key,
 			decrease_clears_refund,
 			has_base_fee,
+			has_prevrandao,
 			disallow_executable_format,
 		} = inputs;
 
-		// See https://eips.ethereum.org/EIPS/eip-2929
-		let gas_sload = gas_storage_read_warm;
-		let gas_sstore_reset = 5000 - gas_sload_cold;
+		let gas_costs = GasCosts::with_access_costs(gas_storage_read_warm, gas_sload_cold);
 
 		// See https://eips.ethereum.org/EIPS/eip-3529
 		let refund_sstore_clears = if decrease_clears_refund {
-			(gas_sstore_reset + gas_access_list_storage_key) as i64
+			(gas_costs.sstore_reset + gas_access_list_storage_key) as i64
 		} else {
 			15000
 		};
 		let max_refund_quotient = if decrease_clears_refund { 5 } else { 2 };
 
 		Config {
-			gas_ext_code: 0,
-			gas_ext_code_hash: 0,
-			gas_balance: 0,
-			gas_sload,
-			gas_sload_cold,
-			gas_sstore_set: 20000,
-			gas_sstore_reset,
+			gas_costs,
 			refund_sstore_clears,
 			max_refund_quotient,
-			gas_suicide: 5000,
-			gas_suicide_new_account: 25000,
-			gas_call: 0,
-			gas_expbyte: 50,
 			gas_transaction_create: 53000,
 			gas_transaction_call: 21000,
 			gas_transaction_zero_data: 4,
 			gas_transaction_non_zero_data: 16,
 			gas_access_list_address: 2400,
 			gas_access_list_storage_key,
-			gas_account_access_cold: 2600,
-			gas_storage_read_warm,
 			sstore_gas_metering: true,
 			sstore_revert_under_stipend: true,
 			increase_state_access_gas: true,
@@ -427,6 +494,10 @@ impl Config {
 			memory_limit: usize::MAX,
 			call_stack_limit: 1024,
 			create_contract_limit: Some(0x6000),
+			max_initcode_size: None,
+			max_call_data_size: None,
+			max_return_data_size: None,
+			max_logs: None,
 			call_stipend: 2300,
 			has_delegate_call: true,
 			has_create2: true,
@@ -437,7 +508,206 @@ impl Config {
 			has_self_balance: true,
 			has_ext_code_hash: true,
 			has_base_fee,
+			has_prevrandao,
+			has_blob_opcodes: false,
+			eip_7702: false,
+			eip_3607: false,
+			bad_jump_consumes_all_gas: true,
+			precompiles_accept_value: true,
+			create2_includes_chain_id: false,
+			hide_deleted_code: false,
+			check_upfront_funds: false,
 			estimate: false,
+			gas_multiplier: 1,
+			disable_gas_metering: false,
+		}
+	}
+}
+
+/// Accessors for the costs that used to be fields of `Config` before they
+/// moved into `Config::gas_costs`.
+impl Config {
+	/// Gas paid for extcode.
+	#[deprecated(note = "use `gas_costs.ext_code`")]
+	pub const fn gas_ext_code(&self) -> u64 {
+		self.gas_costs.ext_code
+	}
+
+	/// Gas paid for extcodehash.
+	#[deprecated(note = "use `gas_costs.ext_code_hash`")]
+	pub const fn gas_ext_code_hash(&self) -> u64 {
+		self.gas_costs.ext_code_hash
+	}
+
+	/// Gas paid for sstore set.
+	#[deprecated(note = "use `gas_costs.sstore_set`")]
+	pub const fn gas_sstore_set(&self) -> u64 {
+		self.gas_costs.sstore_set
+	}
+
+	/// Gas paid for sstore reset.
+	#[deprecated(note = "use `gas_costs.sstore_reset`")]
+	pub const fn gas_sstore_reset(&self) -> u64 {
+		self.gas_costs.sstore_reset
+	}
+
+	/// Gas paid for BALANCE opcode.
+	#[deprecated(note = "use `gas_costs.balance`")]
+	pub const fn gas_balance(&self) -> u64 {
+		self.gas_costs.balance
+	}
+
+	/// Gas paid for SLOAD opcode.
+	#[deprecated(note = "use `gas_costs.sload`")]
+	pub const fn gas_sload(&self) -> u64 {
+		self.gas_costs.sload
+	}
+
+	/// Gas paid for cold SLOAD opcode.
+	#[deprecated(note = "use `gas_costs.sload_cold`")]
+	pub const fn gas_sload_cold(&self) -> u64 {
+		self.gas_costs.sload_cold
+	}
+
+	/// Gas paid for SUICIDE opcode.
+	#[deprecated(note = "use `gas_costs.suicide`")]
+	pub const fn gas_suicide(&self) -> u64 {
+		self.gas_costs.suicide
+	}
+
+	/// Gas paid for SUICIDE opcode when it hits a new account.
+	#[deprecated(note = "use `gas_costs.suicide_new_account`")]
+	pub const fn gas_suicide_new_account(&self) -> u64 {
+		self.gas_costs.suicide_new_account
+	}
+
+	/// Gas paid for CALL opcode.
+	#[deprecated(note = "use `gas_costs.call`")]
+	pub const fn gas_call(&self) -> u64 {
+		self.gas_costs.call
+	}
+
+	/// Gas paid for EXP opcode for every byte.
+	#[deprecated(note = "use `gas_costs.expbyte`")]
+	pub const fn gas_expbyte(&self) -> u64 {
+		self.gas_costs.expbyte
+	}
+
+	/// Gas paid for accessing cold account.
+	#[deprecated(note = "use `gas_costs.account_access_cold`")]
+	pub const fn gas_account_access_cold(&self) -> u64 {
+		self.gas_costs.account_access_cold
+	}
+
+	/// Gas paid for accessing ready storage.
+	#[deprecated(note = "use `gas_costs.storage_read_warm`")]
+	pub const fn gas_storage_read_warm(&self) -> u64 {
+		self.gas_costs.storage_read_warm
+	}
+}
+
+/// Per-opcode base and access gas costs, the values that forks most
+/// commonly reprice.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct GasCosts {
+	/// Gas paid for extcode.
+	pub ext_code: u64,
+	/// Gas paid for extcodehash.
+	pub ext_code_hash: u64,
+	/// Gas paid for sstore set.
+	pub sstore_set: u64,
+	/// Gas paid for sstore reset.
+	pub sstore_reset: u64,
+	/// Gas paid for BALANCE opcode.
+	pub balance: u64,
+	/// Gas paid for SLOAD opcode.
+	pub sload: u64,
+	/// Gas paid for cold SLOAD opcode.
+	pub sload_cold: u64,
+	/// Gas paid for SUICIDE opcode.
+	pub suicide: u64,
+	/// Gas paid for SUICIDE opcode when it hits a new account.
+	pub suicide_new_account: u64,
+	/// Gas paid for CALL opcode.
+	pub call: u64,
+	/// Gas paid for EXP opcode for every byte.
+	pub expbyte: u64,
+	/// Gas paid for accessing cold account.
+	pub account_access_cold: u64,
+	/// Gas paid for accessing ready storage.
+	pub storage_read_warm: u64,
+	/// Gas paid for CREATE and CREATE2 opcode, before hashing the init code.
+	pub create: u64,
+}
+
+impl GasCosts {
+	/// Frontier hard fork costs.
+	pub const fn frontier() -> GasCosts {
+		GasCosts {
+			ext_code: 20,
+			ext_code_hash: 20,
+			sstore_set: 20000,
+			sstore_reset: 5000,
+			balance: 20,
+			sload: 50,
+			sload_cold: 0,
+			suicide: 0,
+			suicide_new_account: 0,
+			call: 40,
+			expbyte: 10,
+			account_access_cold: 0,
+			storage_read_warm: 0,
+			create: 32000,
+		}
+	}
+
+	/// Istanbul hard fork costs.
+	pub const fn istanbul() -> GasCosts {
+		GasCosts {
+			ext_code: 700,
+			ext_code_hash: 700,
+			sstore_set: 20000,
+			sstore_reset: 5000,
+			balance: 700,
+			sload: 800,
+			sload_cold: 0,
+			suicide: 5000,
+			suicide_new_account: 25000,
+			call: 700,
+			expbyte: 50,
+			account_access_cold: 0,
+			storage_read_warm: 0,
+			create: 32000,
+		}
+	}
+
+	/// Berlin hard fork costs, unchanged through the Merge.
+	pub const fn berlin() -> GasCosts {
+		Self::with_access_costs(100, 2100)
+	}
+
+	/// Berlin costs repriced for the given warm storage read and cold `SLOAD`
+	/// costs. As in [EIP-2929](https://eips.ethereum.org/EIPS/eip-2929), a
+	/// warm `SLOAD` costs `storage_read_warm` and the `SSTORE` reset cost is
+	/// `5000 - sload_cold`, so that a cold reset still costs 5000 in total.
+	/// Prefer this over setting those fields one by one, which can leave
+	/// them inconsistent.
+	pub const fn with_access_costs(storage_read_warm: u64, sload_cold: u64) -> GasCosts {
+		GasCosts {
+			ext_code: 0,
+			ext_code_hash: 0,
+			sstore_set: 20000,
+			sstore_reset: 5000u64.saturating_sub(sload_cold),
+			balance: 0,
+			sload: storage_read_warm,
+			sload_cold,
+			suicide: 5000,
+			suicide_new_account: 25000,
+			call: 0,
+			expbyte: 50,
+			account_access_cold: 2600,
+			storage_read_warm,
+			create: 32000,
 		}
 	}
 }
@@ -450,6 +720,7 @@ struct DerivedConfigInputs {
 	gas_access_list_storage_key: u64,
 	decrease_clears_refund: bool,
 	has_base_fee: bool,
+	has_prevrandao: bool,
 	disallow_executable_format: bool,
 }
 
@@ -461,6 +732,7 @@ impl DerivedConfigInputs {
 			gas_access_list_storage_key: 1900,
 			decrease_clears_refund: false,
 			has_base_fee: false,
+			has_prevrandao: false,
 			disallow_executable_format: false,
 		}
 	}
@@ -472,7 +744,56 @@ impl DerivedConfigInputs {
 			gas_access_list_storage_key: 1900,
 			decrease_clears_refund: true,
 			has_base_fee: true,
+			has_prevrandao: false,
 			disallow_executable_format: true,
 		}
 	}
+
+	const fn merge() -> Self {
+		Self {
+			gas_storage_read_warm: 100,
+			gas_sload_cold: 2100,
+			gas_access_list_storage_key: 1900,
+			decrease_clears_refund: true,
+			has_base_fee: true,
+			has_prevrandao: true,
+			disallow_executable_format: true,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::Config;
+
+	#[test]
+	fn london_gas_schedule() {
+		let golden = [
+			("access_list_address", 2400),
+			("access_list_storage_key", 1900),
+			("account_access_cold", 2600),
+			("balance", 0),
+			("call", 0),
+			("call_stipend", 2300),
+			("create", 32000),
+			("expbyte", 50),
+			("ext_code", 0),
+			("ext_code_hash", 0),
+			("max_refund_quotient", 5),
+			("sload", 100),
+			("sload_cold", 2100),
+			("sstore_reset", 2900),
+			("sstore_set", 20000),
+			("storage_read_warm", 100),
+			("suicide", 5000),
+			("suicide_new_account", 25000),
+			("transaction_call", 21000),
+			("transaction_create", 53000),
+			("transaction_non_zero_data", 16),
+			("transaction_zero_data", 4),
+		];
+
+		let snapshot = Config::london().gas_schedule_snapshot();
+		assert_eq!(snapshot.into_iter().collect::<Vec<_>>(), golden.to_vec());
+	}
 }
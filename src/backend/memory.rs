@@ -2,9 +2,10 @@ use super::{Apply, ApplyBackend, Backend, Basic, Log};
 use alloc::collections::BTreeMap;
 use alloc::vec::Vec;
 use primitive_types::{H160, H256, U256};
+use sha3::{Digest, Keccak256};
 
 /// Vicinity value of a memory backend.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Default, Clone, Debug, Eq, PartialEq)]
 #[cfg_attr(
 	feature = "with-codec",
 	derive(scale_codec::Encode, scale_codec::Decode, scale_info::TypeInfo)
@@ -27,10 +28,18 @@ pub struct MemoryVicinity {
 	pub block_timestamp: U256,
 	/// Environmental block difficulty.
 	pub block_difficulty: U256,
+	/// Environmental block randomness.
+	pub block_randomness: Option<H256>,
 	/// Environmental block gas limit.
 	pub block_gas_limit: U256,
 	/// Environmental base fee per gas.
 	pub block_base_fee_per_gas: U256,
+	/// Environmental blob base fee.
+	#[cfg_attr(feature = "with-serde", serde(default))]
+	pub blob_base_fee: U256,
+	/// Versioned hashes of the transaction blobs.
+	#[cfg_attr(feature = "with-serde", serde(default))]
+	pub blob_hashes: Vec<H256>,
 }
 
 /// Account information of a memory backend.
@@ -56,7 +65,9 @@ pub struct MemoryAccount {
 pub struct MemoryBackend<'vicinity> {
 	vicinity: &'vicinity MemoryVicinity,
 	state: BTreeMap<H160, MemoryAccount>,
+	block_hashes: BTreeMap<U256, H256>,
 	logs: Vec<Log>,
+	created_accounts: Vec<H160>,
 }
 
 impl<'vicinity> MemoryBackend<'vicinity> {
@@ -65,7 +76,9 @@ impl<'vicinity> MemoryBackend<'vicinity> {
 		Self {
 			vicinity,
 			state,
+			block_hashes: BTreeMap::new(),
 			logs: Vec::new(),
+			created_accounts: Vec::new(),
 		}
 	}
 
@@ -78,6 +91,34 @@ impl<'vicinity> MemoryBackend<'vicinity> {
 	pub fn state_mut(&mut self) -> &mut BTreeMap<H160, MemoryAccount> {
 		&mut self.state
 	}
+
+	/// Accounts created by `apply`, in the order they were applied. Within a
+	/// single `apply` call the order is that of the applied values.
+	pub fn created_accounts(&self) -> &[H160] {
+		&self.created_accounts
+	}
+
+	/// Commitment to the storage of an account.
+	///
+	/// This is a simplified commitment rather than a Merkle-Patricia trie
+	/// root: the Keccak-256 hash of the concatenated `(slot, value)` pairs,
+	/// in ascending slot order.
+	pub fn storage_root(&self, address: H160) -> H256 {
+		let mut hasher = Keccak256::new();
+		if let Some(account) = self.state.get(&address) {
+			for (slot, value) in &account.storage {
+				hasher.update(slot.as_bytes());
+				hasher.update(value.as_bytes());
+			}
+		}
+		H256::from_slice(hasher.finalize().as_slice())
+	}
+
+	/// Seed the hash of a block, taking precedence over the vicinity's
+	/// `block_hashes`. It is still only served for the 256 most recent blocks.
+	pub fn set_block_hash(&mut self, number: U256, hash: H256) {
+		self.block_hashes.insert(number, hash);
+	}
 }
 
 impl<'vicinity> Backend for MemoryBackend<'vicinity> {
@@ -89,8 +130,15 @@ impl<'vicinity> Backend for MemoryBackend<'vicinity> {
 	}
 	fn block_hash(&self, number: U256) -> H256 {
 		if number >= self.vicinity.block_number
-			|| self.vicinity.block_number - number - U256::one()
-				>= U256::from(self.vicinity.block_hashes.len())
+			|| self.vicinity.block_number - number > U256::from(256)
+		{
+			return H256::default();
+		}
+
+		if let Some(hash) = self.block_hashes.get(&number) {
+			*hash
+		} else if self.vicinity.block_number - number - U256::one()
+			>= U256::from(self.vicinity.block_hashes.len())
 		{
 			H256::default()
 		} else {
@@ -110,12 +158,25 @@ impl<'vicinity> Backend for MemoryBackend<'vicinity> {
 	fn block_difficulty(&self) -> U256 {
 		self.vicinity.block_difficulty
 	}
+	fn block_randomness(&self) -> Option<H256> {
+		self.vicinity.block_randomness
+	}
 	fn block_gas_limit(&self) -> U256 {
 		self.vicinity.block_gas_limit
 	}
 	fn block_base_fee_per_gas(&self) -> U256 {
 		self.vicinity.block_base_fee_per_gas
 	}
+	fn blob_base_fee(&self) -> U256 {
+		self.vicinity.blob_base_fee
+	}
+	fn blob_hash(&self, index: U256) -> H256 {
+		if index >= U256::from(self.vicinity.blob_hashes.len()) {
+			H256::default()
+		} else {
+			self.vicinity.blob_hashes[index.as_usize()]
+		}
+	}
 
 	fn chain_id(&self) -> U256 {
 		self.vicinity.chain_id
@@ -152,6 +213,13 @@ impl<'vicinity> Backend for MemoryBackend<'vicinity> {
 	fn original_storage(&self, address: H160, index: H256) -> Option<H256> {
 		Some(self.storage(address, index))
 	}
+
+	fn storage_entries(&self, address: H160) -> BTreeMap<H256, H256> {
+		self.state
+			.get(&address)
+			.map(|account| account.storage.clone())
+			.unwrap_or_default()
+	}
 }
 
 impl<'vicinity> ApplyBackend for MemoryBackend<'vicinity> {
@@ -170,6 +238,7 @@ impl<'vicinity> ApplyBackend for MemoryBackend<'vicinity> {
 					storage,
 					reset_storage,
 				} => {
+					let is_new = !self.state.contains_key(&address);
 					let is_empty = {
 						let account = self.state.entry(address).or_insert_with(Default::default);
 						account.balance = basic.balance;
@@ -208,6 +277,8 @@ impl<'vicinity> ApplyBackend for MemoryBackend<'vicinity> {
 
 					if is_empty && delete_empty {
 						self.state.remove(&address);
+					} else if is_new {
+						self.created_accounts.push(address);
 					}
 				}
 				Apply::Delete { address } => {
@@ -221,3 +292,151 @@ impl<'vicinity> ApplyBackend for MemoryBackend<'vicinity> {
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::{MemoryAccount, MemoryBackend, MemoryVicinity};
+	use crate::backend::ApplyBackend;
+	use crate::executor::stack::{MemoryStackState, StackExecutor, StackSubstateMetadata};
+	use crate::test_utils::vicinity;
+	use crate::{Config, CreateScheme, ExitReason, ExitSucceed};
+	use alloc::collections::BTreeMap;
+	use primitive_types::{H160, H256, U256};
+
+	fn blockhash(backend: &MemoryBackend, number: u8) -> Vec<u8> {
+		let config = Config::istanbul();
+		let metadata = StackSubstateMetadata::new(u64::MAX, &config);
+		let state = MemoryStackState::new(metadata, backend);
+		let precompiles = BTreeMap::new();
+		let mut executor = StackExecutor::new_with_precompiles(state, &config, &precompiles);
+
+		let (reason, output) = executor.transact_call(
+			H160::repeat_byte(0x01),
+			H160::repeat_byte(0x02),
+			U256::zero(),
+			vec![number],
+			1_000_000,
+			Vec::new(),
+		);
+		assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Returned));
+		output
+	}
+
+	#[test]
+	fn seeded_block_hash() {
+		let vicinity = MemoryVicinity {
+			block_number: U256::from(100),
+			..vicinity()
+		};
+		let mut state = BTreeMap::new();
+		state.insert(
+			H160::repeat_byte(0x02),
+			MemoryAccount {
+				// PUSH1 0, CALLDATALOAD, PUSH1 248, SHR, BLOCKHASH,
+				// PUSH1 0, MSTORE, PUSH1 32, PUSH1 0, RETURN
+				code: vec![
+					0x60, 0x00, 0x35, 0x60, 0xf8, 0x1c, 0x40, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60,
+					0x00, 0xf3,
+				],
+				..Default::default()
+			},
+		);
+		let mut backend = MemoryBackend::new(&vicinity, state);
+		let hash = H256::repeat_byte(0xaa);
+		backend.set_block_hash(U256::from(99), hash);
+
+		assert_eq!(blockhash(&backend, 99), hash.as_bytes());
+		assert_eq!(blockhash(&backend, 98), H256::default().as_bytes());
+	}
+
+	#[test]
+	fn block_hash_window() {
+		// Hashes of blocks 299 down to 43, most recent first.
+		let hashes = (0..257).map(H256::from_low_u64_be).collect::<Vec<_>>();
+		let vicinity = MemoryVicinity {
+			block_number: U256::from(300),
+			block_hashes: hashes.clone(),
+			..vicinity()
+		};
+		let mut state = BTreeMap::new();
+		state.insert(
+			H160::repeat_byte(0x02),
+			MemoryAccount {
+				// PUSH1 0, CALLDATALOAD, PUSH1 248, SHR, BLOCKHASH,
+				// PUSH1 0, MSTORE, PUSH1 32, PUSH1 0, RETURN
+				code: vec![
+					0x60, 0x00, 0x35, 0x60, 0xf8, 0x1c, 0x40, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60,
+					0x00, 0xf3,
+				],
+				..Default::default()
+			},
+		);
+		let mut backend = MemoryBackend::new(&vicinity, state);
+
+		// Block 44 is 256 blocks back, the oldest one served.
+		assert_eq!(blockhash(&backend, 44), hashes[255].as_bytes());
+		// Block 43 is out of the window, even though its hash is known.
+		assert_eq!(blockhash(&backend, 43), H256::default().as_bytes());
+		backend.set_block_hash(U256::from(43), H256::repeat_byte(0xaa));
+		assert_eq!(blockhash(&backend, 43), H256::default().as_bytes());
+	}
+
+	#[test]
+	fn created_accounts_in_order() {
+		let vicinity = vicinity();
+		let caller = H160::repeat_byte(0x01);
+		let mut state = BTreeMap::new();
+		state.insert(caller, MemoryAccount::default());
+		let mut backend = MemoryBackend::new(&vicinity, state);
+		let config = Config::istanbul();
+		let precompiles = BTreeMap::new();
+
+		let mut expected = Vec::new();
+		for _ in 0..2 {
+			let metadata = StackSubstateMetadata::new(u64::MAX, &config);
+			let state = MemoryStackState::new(metadata, &backend);
+			let mut executor = StackExecutor::new_with_precompiles(state, &config, &precompiles);
+			expected.push(executor.create_address(CreateScheme::Legacy { caller }));
+
+			// PUSH1 1, PUSH1 0, RETURN: deploys a single STOP.
+			let init_code = vec![0x60, 0x01, 0x60, 0x00, 0xf3];
+			let (reason, _) =
+				executor.transact_create(caller, U256::zero(), init_code, 1_000_000, Vec::new());
+			assert_eq!(reason, ExitReason::Succeed(ExitSucceed::Returned));
+
+			let (values, logs) = executor.into_state().deconstruct();
+			backend.apply(values, logs, false);
+		}
+
+		assert_eq!(backend.created_accounts(), &expected[..]);
+	}
+
+	#[test]
+	fn storage_root() {
+		let vicinity = vicinity();
+		let address = H160::repeat_byte(0x01);
+		let account = |value: u8| MemoryAccount {
+			storage: vec![
+				(H256::repeat_byte(0x01), H256::repeat_byte(0x11)),
+				(H256::repeat_byte(0x02), H256::repeat_byte(value)),
+			]
+			.into_iter()
+			.collect(),
+			..Default::default()
+		};
+		let backend = |value: u8| {
+			let mut state = BTreeMap::new();
+			state.insert(address, account(value));
+			MemoryBackend::new(&vicinity, state)
+		};
+
+		assert_eq!(
+			backend(0x22).storage_root(address),
+			backend(0x22).storage_root(address)
+		);
+		assert_ne!(
+			backend(0x22).storage_root(address),
+			backend(0x23).storage_root(address)
+		);
+	}
+}
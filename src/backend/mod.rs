@@ -6,7 +6,7 @@ mod memory;
 
 pub use self::memory::{MemoryAccount, MemoryBackend, MemoryVicinity};
 
-use alloc::vec::Vec;
+use alloc::{collections::BTreeMap, vec::Vec};
 use primitive_types::{H160, H256, U256};
 
 /// Basic account information.
@@ -66,10 +66,29 @@ pub trait Backend {
 	fn block_timestamp(&self) -> U256;
 	/// Environmental block difficulty.
 	fn block_difficulty(&self) -> U256;
+	/// Environmental block randomness, replacing the difficulty after the merge.
+	///
+	/// Defaults to `None`.
+	fn block_randomness(&self) -> Option<H256> {
+		None
+	}
 	/// Environmental block gas limit.
 	fn block_gas_limit(&self) -> U256;
 	/// Environmental block base fee.
 	fn block_base_fee_per_gas(&self) -> U256;
+	/// Environmental blob base fee.
+	///
+	/// Defaults to zero.
+	fn blob_base_fee(&self) -> U256 {
+		U256::zero()
+	}
+	/// Versioned hash of the transaction blob at `index`, or zero if out of
+	/// range.
+	///
+	/// Defaults to zero for every index.
+	fn blob_hash(&self, _index: U256) -> H256 {
+		H256::zero()
+	}
 	/// Environmental chain ID.
 	fn chain_id(&self) -> U256;
 
@@ -83,6 +102,11 @@ pub trait Backend {
 	fn storage(&self, address: H160, index: H256) -> H256;
 	/// Get original storage value of address at index, if available.
 	fn original_storage(&self, address: H160, index: H256) -> Option<H256>;
+	/// Non-zero storage entries of address. Backends that cannot enumerate
+	/// storage return an empty map.
+	fn storage_entries(&self, _address: H160) -> BTreeMap<H256, H256> {
+		BTreeMap::new()
+	}
 }
 
 /// EVM backend that can apply changes.
@@ -1,4 +1,5 @@
-use crate::backend::Backend;
+use super::MemoryStackState;
+use crate::backend::{Apply, ApplyBackend, Backend, Log};
 use crate::gasometer::{self, Gasometer, StorageTarget};
 use crate::{
 	Capture, Config, Context, CreateScheme, ExitError, ExitReason, ExitSucceed, Handler, Opcode,
@@ -9,11 +10,14 @@ use alloc::{
 	rc::Rc,
 	vec::Vec,
 };
-use core::{cmp::min, convert::Infallible};
-use evm_core::{ExitFatal, ExitRevert};
+use core::{cmp::min, convert::Infallible, mem};
+use evm_core::{rlp, u256_to_be_bytes, u256_to_minimal_be_bytes, ExitFatal, ExitRevert};
 use primitive_types::{H160, H256, U256};
 use sha3::{Digest, Keccak256};
 
+/// Code prefix of an EIP-7702 delegation indicator, followed by the address.
+const DELEGATION_PREFIX: [u8; 3] = [0xef, 0x01, 0x00];
+
 macro_rules! emit_exit {
 	($reason:expr) => {{
 		let reason = $reason;
@@ -34,6 +38,119 @@ macro_rules! emit_exit {
 	}};
 }
 
+/// Outcome of a call at the executor boundary.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CallOutcome {
+	/// The call succeeded, with its output.
+	Success(Vec<u8>),
+	/// The call reverted, with its revert data.
+	Revert(Vec<u8>),
+	/// The call failed with a normal EVM error.
+	Error(ExitError),
+	/// The call failed with a fatal error.
+	Fatal(ExitFatal),
+}
+
+impl From<(ExitReason, Vec<u8>)> for CallOutcome {
+	fn from((reason, output): (ExitReason, Vec<u8>)) -> Self {
+		match reason {
+			ExitReason::Succeed(_) => Self::Success(output),
+			ExitReason::Revert(_) => Self::Revert(output),
+			ExitReason::Error(e) => Self::Error(e),
+			ExitReason::Fatal(e) => Self::Fatal(e),
+		}
+	}
+}
+
+/// An entry of an EIP-2930 access list.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct AccessListItem {
+	pub address: H160,
+	pub storage_keys: Vec<H256>,
+}
+
+/// An EIP-2930 access list, accepted by the `transact_*` methods alongside
+/// plain `(address, storage_keys)` pairs.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct AccessList(pub Vec<AccessListItem>);
+
+impl From<Vec<(H160, Vec<H256>)>> for AccessList {
+	fn from(pairs: Vec<(H160, Vec<H256>)>) -> Self {
+		Self(
+			pairs
+				.into_iter()
+				.map(|(address, storage_keys)| AccessListItem {
+					address,
+					storage_keys,
+				})
+				.collect(),
+		)
+	}
+}
+
+impl From<AccessList> for Vec<(H160, Vec<H256>)> {
+	fn from(access_list: AccessList) -> Self {
+		access_list
+			.0
+			.into_iter()
+			.map(|item| (item.address, item.storage_keys))
+			.collect()
+	}
+}
+
+/// Block values seen by executed code, for running under a spoofed block
+/// with `StackExecutor::with_block_override`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BlockContext {
+	pub number: U256,
+	pub coinbase: H160,
+	pub timestamp: U256,
+	pub difficulty: U256,
+	pub randomness: Option<H256>,
+	pub gas_limit: U256,
+	pub base_fee_per_gas: U256,
+	pub blob_base_fee: U256,
+	pub chain_id: U256,
+}
+
+/// A transaction to be applied by `StackExecutor::transact_many`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Transaction {
+	/// A `CALL` transaction.
+	Call {
+		caller: H160,
+		address: H160,
+		value: U256,
+		data: Vec<u8>,
+		gas_limit: u64,
+		access_list: Vec<(H160, Vec<H256>)>,
+	},
+	/// A `CREATE` transaction.
+	Create {
+		caller: H160,
+		value: U256,
+		init_code: Vec<u8>,
+		gas_limit: u64,
+		access_list: Vec<(H160, Vec<H256>)>,
+	},
+}
+
+impl Transaction {
+	/// Sender of the transaction.
+	pub fn caller(&self) -> H160 {
+		match self {
+			Self::Call { caller, .. } | Self::Create { caller, .. } => *caller,
+		}
+	}
+
+	/// Gas limit of the transaction.
+	pub fn gas_limit(&self) -> u64 {
+		match self {
+			Self::Call { gas_limit, .. } | Self::Create { gas_limit, .. } => *gas_limit,
+		}
+	}
+}
+
 pub enum StackExitKind {
 	Succeeded,
 	Reverted,
@@ -97,6 +214,8 @@ impl<'config> StackSubstateMetadata<'config> {
 		self.gasometer.record_stipend(other.gasometer.gas())?;
 		self.gasometer
 			.record_refund(other.gasometer.refunded_gas())?;
+		self.gasometer
+			.record_nominal_gas(other.gasometer.nominal_gas())?;
 
 		if let (Some(mut other_accessed), Some(self_accessed)) =
 			(other.accessed, self.accessed.as_mut())
@@ -114,6 +233,8 @@ impl<'config> StackSubstateMetadata<'config> {
 
 	pub fn swallow_revert(&mut self, other: Self) -> Result<(), ExitError> {
 		self.gasometer.record_stipend(other.gasometer.gas())?;
+		self.gasometer
+			.record_nominal_gas(other.gasometer.nominal_gas())?;
 
 		Ok(())
 	}
@@ -134,6 +255,17 @@ impl<'config> StackSubstateMetadata<'config> {
 		}
 	}
 
+	/// Metadata of a substate checkpointing a whole transaction. Unlike
+	/// `spit_child`, it is not a call frame: depth and static flag are kept.
+	pub fn spit_checkpoint(&self, gas_limit: u64) -> Self {
+		Self {
+			gasometer: Gasometer::new(gas_limit, self.gasometer.config()),
+			is_static: self.is_static,
+			depth: self.depth,
+			accessed: self.accessed.as_ref().map(|_| Accessed::default()),
+		}
+	}
+
 	pub fn gasometer(&self) -> &Gasometer<'config> {
 		&self.gasometer
 	}
@@ -183,6 +315,14 @@ impl<'config> StackSubstateMetadata<'config> {
 	pub fn accessed(&self) -> &Option<Accessed> {
 		&self.accessed
 	}
+
+	/// Forget all accessed addresses and storage slots, so that they are
+	/// cold again.
+	pub fn clear_accessed(&mut self) {
+		if let Some(accessed) = &mut self.accessed {
+			*accessed = Accessed::default();
+		}
+	}
 }
 
 #[auto_impl::auto_impl(&mut, Box)]
@@ -191,6 +331,12 @@ pub trait StackState<'config>: Backend {
 	fn metadata_mut(&mut self) -> &mut StackSubstateMetadata<'config>;
 
 	fn enter(&mut self, gas_limit: u64, is_static: bool);
+	/// Enter a substate checkpointing a whole transaction, which is not a
+	/// call frame and so does not count toward the call depth. Defaults to a
+	/// non-static `enter`, which does count.
+	fn enter_checkpoint(&mut self, gas_limit: u64) {
+		self.enter(gas_limit, false)
+	}
 	fn exit_commit(&mut self) -> Result<(), ExitError>;
 	fn exit_revert(&mut self) -> Result<(), ExitError>;
 	fn exit_discard(&mut self) -> Result<(), ExitError>;
@@ -200,10 +346,44 @@ pub trait StackState<'config>: Backend {
 	fn is_cold(&self, address: H160) -> bool;
 	fn is_storage_cold(&self, address: H160, key: H256) -> bool;
 
+	#[deprecated(note = "use `try_inc_nonce`, which rejects a nonce overflow")]
 	fn inc_nonce(&mut self, address: H160);
+	/// Increment the nonce of an account. Fails with `MaxNonce`, leaving the
+	/// nonce as is, when it is already `u64::MAX` (EIP-2681).
+	fn try_inc_nonce(&mut self, address: H160) -> Result<(), ExitError> {
+		if self.basic(address).nonce >= U256::from(u64::MAX) {
+			return Err(ExitError::MaxNonce);
+		}
+		self.inc_nonce(address);
+
+		Ok(())
+	}
 	fn set_storage(&mut self, address: H160, key: H256, value: H256);
 	fn reset_storage(&mut self, address: H160);
 	fn log(&mut self, address: H160, topics: Vec<H256>, data: Vec<u8>);
+	/// Logs emitted so far, including in parent substates, in emission
+	/// order. Defaults to none.
+	fn logs(&self) -> Vec<Log> {
+		Vec::new()
+	}
+	/// Number of logs emitted so far, including in parent substates.
+	/// Defaults to the length of `logs`.
+	fn log_count(&self) -> usize {
+		self.logs().len()
+	}
+	/// Accounts whose balance, nonce, code or storage changed, or that were
+	/// deleted, so far, including in parent substates. Accounts only read,
+	/// touched or sent zero value are left out. Defaults to none.
+	fn touched_accounts(&self) -> BTreeSet<H160> {
+		BTreeSet::new()
+	}
+	/// Whether the current substate, including child substates committed
+	/// into it, wrote storage, changed a balance, nonce or code, emitted a
+	/// log or deleted an account. Defaults to `true`, which is always safe to
+	/// assume.
+	fn made_state_change(&self) -> bool {
+		true
+	}
 	fn set_deleted(&mut self, address: H160);
 	fn set_code(&mut self, address: H160, code: Vec<u8>);
 	fn transfer(&mut self, transfer: Transfer) -> Result<(), ExitError>;
@@ -218,6 +398,12 @@ pub trait StackState<'config>: Backend {
 		U256::from(self.code(address).len())
 	}
 
+	/// Whether a contract can be created at an address, that is the address
+	/// has neither code nor a nonzero nonce.
+	fn is_creatable(&self, address: H160) -> bool {
+		self.code_size(address) == U256::zero() && self.basic(address).nonce == U256::zero()
+	}
+
 	/// Fetch the code hash of an address.
 	/// Provide a default implementation by fetching the code, but
 	/// can be customized to use a more performant approach that don't need to
@@ -275,7 +461,7 @@ pub trait PrecompileHandle {
 	/// Retreive the remaining gas.
 	fn remaining_gas(&self) -> u64;
 
-	/// Record a log.
+	/// Record a log. Fails in a static call.
 	fn log(&mut self, address: H160, topics: Vec<H256>, data: Vec<u8>) -> Result<(), ExitError>;
 
 	/// Retreive the code address (what is the address of the precompile being called).
@@ -292,6 +478,18 @@ pub trait PrecompileHandle {
 
 	/// Retreive the gas limit of this call.
 	fn gas_limit(&self) -> Option<u64>;
+
+	/// Whether `address` is already executing in an enclosing frame, so that
+	/// calling it again would re-enter it. Defaults to `false`.
+	fn is_reentrant(&self, _address: H160) -> bool {
+		false
+	}
+
+	/// Depth of the call frame running the precompile, `0` when it is called
+	/// by the transaction itself. Defaults to `0`.
+	fn depth(&self) -> usize {
+		0
+	}
 }
 
 /// A precompile result.
@@ -359,11 +557,168 @@ impl PrecompileSet for BTreeMap<H160, PrecompileFn> {
 	}
 }
 
+/// Precompiles paired with their minimum base cost. The base cost is recorded
+/// before the precompile is invoked, so underfunded calls fail with `OutOfGas`
+/// without running it. The cost returned by the precompile is the total cost,
+/// base cost included.
+#[derive(Clone, Debug, Default)]
+pub struct BaseCostPrecompileSet(pub BTreeMap<H160, (PrecompileFn, u64)>);
+
+impl PrecompileSet for BaseCostPrecompileSet {
+	fn execute(&self, handle: &mut impl PrecompileHandle) -> Option<PrecompileResult> {
+		let address = handle.code_address();
+
+		self.0.get(&address).map(|(precompile, base_cost)| {
+			handle.record_cost(*base_cost)?;
+
+			let input = handle.input();
+			let gas_limit = handle.gas_limit();
+			let context = handle.context();
+			let is_static = handle.is_static();
+
+			match (*precompile)(input, gas_limit, context, is_static) {
+				Ok((output, cost)) => {
+					handle.record_cost(cost.saturating_sub(*base_cost))?;
+					Ok(output)
+				}
+				Err(err) => Err(err),
+			}
+		})
+	}
+
+	fn is_precompile(&self, address: H160) -> bool {
+		self.0.contains_key(&address)
+	}
+}
+
+/// Slots that differ between two storage snapshots, as `(slot, old, new)`
+/// in ascending slot order. Missing slots read as zero.
+pub fn storage_diff(
+	before: &BTreeMap<H256, H256>,
+	after: &BTreeMap<H256, H256>,
+) -> Vec<(H256, H256, H256)> {
+	let slots: BTreeSet<&H256> = before.keys().chain(after.keys()).collect();
+
+	slots
+		.into_iter()
+		.filter_map(|slot| {
+			let old = before.get(slot).copied().unwrap_or_default();
+			let new = after.get(slot).copied().unwrap_or_default();
+			if old == new {
+				None
+			} else {
+				Some((*slot, old, new))
+			}
+		})
+		.collect()
+}
+
+/// Address the code passed to `run_code` runs at.
+pub const RUN_CODE_ADDRESS: H160 = H160([
+	0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+	0x00, 0x00, 0xc0, 0xde,
+]);
+
+/// Run `code` with `input` as call data, as if called at `RUN_CODE_ADDRESS` by
+/// the zero address with no value, and apply the resulting state changes to
+/// `backend`. The call goes through the same path as a `CALL` into an account
+/// holding `code`, metered against the block gas limit but without intrinsic
+/// transaction cost, and no precompiles are available. `code` itself is not
+/// stored in `backend`. Meant for quick experiments; use `StackExecutor`
+/// directly for anything else.
+pub fn run_code<B: Backend + ApplyBackend>(
+	code: Vec<u8>,
+	input: Vec<u8>,
+	backend: &mut B,
+	config: &Config,
+) -> (ExitReason, Vec<u8>) {
+	let gas_limit = min(backend.block_gas_limit(), U256::from(u64::MAX)).as_u64();
+	let context = Context::top_level(H160::zero(), RUN_CODE_ADDRESS, U256::zero());
+
+	let (result, values, logs) = {
+		let metadata = StackSubstateMetadata::new(gas_limit, config);
+		let state = MemoryStackState::new(metadata, &*backend);
+		let mut executor = StackExecutor::new_with_precompiles(state, config, &());
+		executor.state_mut().set_code(RUN_CODE_ADDRESS, code);
+
+		let result = match executor.call_inner(
+			RUN_CODE_ADDRESS,
+			None,
+			input,
+			Some(gas_limit),
+			false,
+			false,
+			false,
+			context,
+		) {
+			Capture::Exit(result) => result,
+			Capture::Trap(_) => unreachable!(),
+		};
+		let (values, logs) = executor.into_state().deconstruct();
+		(
+			result,
+			values.into_iter().collect::<Vec<_>>(),
+			logs.into_iter().collect::<Vec<_>>(),
+		)
+	};
+
+	if result.0.is_succeed() {
+		let original = backend.basic(RUN_CODE_ADDRESS);
+		let values = values
+			.into_iter()
+			.filter_map(|apply| match apply {
+				Apply::Modify {
+					address,
+					basic,
+					storage,
+					reset_storage,
+					..
+				} if address == RUN_CODE_ADDRESS => {
+					// Keep the changes made by the code, but not the code.
+					let storage = storage.into_iter().collect::<Vec<_>>();
+					if storage.is_empty() && !reset_storage && basic == original {
+						None
+					} else {
+						Some(Apply::Modify {
+							address,
+							basic,
+							code: None,
+							storage,
+							reset_storage,
+						})
+					}
+				}
+				Apply::Modify {
+					address,
+					basic,
+					code,
+					storage,
+					reset_storage,
+				} => Some(Apply::Modify {
+					address,
+					basic,
+					code,
+					storage: storage.into_iter().collect(),
+					reset_storage,
+				}),
+				Apply::Delete { address } => Some(Apply::Delete { address }),
+			})
+			.collect::<Vec<_>>();
+		backend.apply(values, logs, false);
+	}
+	result
+}
+
 /// Stack-based executor.
 pub struct StackExecutor<'config, 'precompiles, S, P> {
 	config: &'config Config,
 	state: S,
 	precompile_set: &'precompiles P,
+	call_stack: Vec<H160>,
+	block_override: Option<BlockContext>,
+	/// Log count when the current transaction started, so that
+	/// `Config::max_logs` only counts the logs of that transaction.
+	transaction_log_start: usize,
 }
 
 impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
@@ -389,6 +744,9 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 			config,
 			state,
 			precompile_set,
+			call_stack: Vec::new(),
+			block_override: None,
+			transaction_log_start: 0,
 		}
 	}
 
@@ -431,14 +789,47 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 		self.state.metadata().gasometer.gas()
 	}
 
+	/// Code addresses of the frames currently executing, outermost first.
+	pub fn call_stack_addresses(&self) -> Vec<H160> {
+		self.call_stack.clone()
+	}
+
+	/// Whether `address` is already executing in an enclosing frame, so that
+	/// entering it again is a reentrancy.
+	pub fn is_reentrant(&self, address: H160) -> bool {
+		self.call_stack.contains(&address)
+	}
+
+	/// Depth of the current call frame, `0` for the outermost call.
+	pub fn depth(&self) -> usize {
+		self.state.metadata().depth().unwrap_or(0)
+	}
+
+	/// Record the intrinsic cost of a transaction. A `gas_limit` below the
+	/// intrinsic cost fails with `OutOfGas` before anything is recorded.
+	fn record_transaction_cost(
+		&mut self,
+		transaction_cost: gasometer::TransactionCost,
+		gas_limit: u64,
+	) -> Result<(), ExitError> {
+		if transaction_cost.gas_cost(self.config) > gas_limit {
+			return Err(ExitError::OutOfGas);
+		}
+
+		let gasometer = &mut self.state.metadata_mut().gasometer;
+		gasometer.record_transaction(transaction_cost)
+	}
+
 	fn record_create_transaction_cost(
 		&mut self,
 		init_code: &[u8],
+		gas_limit: u64,
 		access_list: &[(H160, Vec<H256>)],
 	) -> Result<(), ExitError> {
+		gasometer::check_initcode_size(U256::from(init_code.len()), self.config)?;
+
 		let transaction_cost = gasometer::create_transaction_cost(init_code, access_list);
-		let gasometer = &mut self.state.metadata_mut().gasometer;
-		gasometer.record_transaction(transaction_cost)
+		self.record_transaction_cost(transaction_cost, gas_limit)
 	}
 
 	/// Execute a `CREATE` transaction.
@@ -448,8 +839,9 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 		value: U256,
 		init_code: Vec<u8>,
 		gas_limit: u64,
-		access_list: Vec<(H160, Vec<H256>)>, // See EIP-2930
+		access_list: impl Into<AccessList>, // See EIP-2930
 	) -> (ExitReason, Vec<u8>) {
+		let access_list = Vec::from(access_list.into());
 		event!(TransactCreate {
 			caller,
 			value,
@@ -458,10 +850,20 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 			address: self.create_address(CreateScheme::Legacy { caller }),
 		});
 
-		if let Err(e) = self.record_create_transaction_cost(&init_code, &access_list) {
+		if let Err(e) = self.check_sender(caller) {
+			return emit_exit!(e.into(), Vec::new());
+		}
+		if let Err(e) = self.check_nonce(caller) {
+			return emit_exit!(e.into(), Vec::new());
+		}
+		if let Err(e) = self.check_funds(caller, gas_limit, value) {
+			return emit_exit!(e.into(), Vec::new());
+		}
+		if let Err(e) = self.record_create_transaction_cost(&init_code, gas_limit, &access_list) {
 			return emit_exit!(e.into(), Vec::new());
 		}
 		self.initialize_with_access_list(access_list);
+		self.transaction_log_start = self.state.log_count();
 
 		match self.create_inner(
 			caller,
@@ -484,8 +886,9 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 		init_code: Vec<u8>,
 		salt: H256,
 		gas_limit: u64,
-		access_list: Vec<(H160, Vec<H256>)>, // See EIP-2930
+		access_list: impl Into<AccessList>, // See EIP-2930
 	) -> (ExitReason, Vec<u8>) {
+		let access_list = Vec::from(access_list.into());
 		let code_hash = H256::from_slice(Keccak256::digest(&init_code).as_slice());
 		event!(TransactCreate2 {
 			caller,
@@ -500,10 +903,20 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 			}),
 		});
 
-		if let Err(e) = self.record_create_transaction_cost(&init_code, &access_list) {
+		if let Err(e) = self.check_sender(caller) {
+			return emit_exit!(e.into(), Vec::new());
+		}
+		if let Err(e) = self.check_nonce(caller) {
+			return emit_exit!(e.into(), Vec::new());
+		}
+		if let Err(e) = self.check_funds(caller, gas_limit, value) {
+			return emit_exit!(e.into(), Vec::new());
+		}
+		if let Err(e) = self.record_create_transaction_cost(&init_code, gas_limit, &access_list) {
 			return emit_exit!(e.into(), Vec::new());
 		}
 		self.initialize_with_access_list(access_list);
+		self.transaction_log_start = self.state.log_count();
 
 		match self.create_inner(
 			caller,
@@ -526,8 +939,9 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 	/// gas limit and data.
 	///
 	/// Takes in an additional `access_list` parameter for EIP-2930 which was
-	/// introduced in the Ethereum Berlin hard fork. If you do not wish to use
-	/// this functionality, just pass in an empty vector.
+	/// introduced in the Ethereum Berlin hard fork, either as an `AccessList`
+	/// or as `(address, storage_keys)` pairs. If you do not wish to use this
+	/// functionality, just pass in an empty vector.
 	pub fn transact_call(
 		&mut self,
 		caller: H160,
@@ -535,8 +949,9 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 		value: U256,
 		data: Vec<u8>,
 		gas_limit: u64,
-		access_list: Vec<(H160, Vec<H256>)>,
+		access_list: impl Into<AccessList>,
 	) -> (ExitReason, Vec<u8>) {
+		let access_list = Vec::from(access_list.into());
 		event!(TransactCall {
 			caller,
 			address,
@@ -545,11 +960,19 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 			gas_limit,
 		});
 
+		if let Err(e) = self.check_sender(caller) {
+			return emit_exit!(e.into(), Vec::new());
+		}
+		if let Err(e) = self.check_nonce(caller) {
+			return emit_exit!(e.into(), Vec::new());
+		}
+		if let Err(e) = self.check_funds(caller, gas_limit, value) {
+			return emit_exit!(e.into(), Vec::new());
+		}
+
 		let transaction_cost = gasometer::call_transaction_cost(&data, &access_list);
-		let gasometer = &mut self.state.metadata_mut().gasometer;
-		match gasometer.record_transaction(transaction_cost) {
-			Ok(()) => (),
-			Err(e) => return emit_exit!(e.into(), Vec::new()),
+		if let Err(e) = self.record_transaction_cost(transaction_cost, gas_limit) {
+			return emit_exit!(e.into(), Vec::new());
 		}
 
 		// Initialize initial addresses for EIP-2929
@@ -560,13 +983,12 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 			self.initialize_with_access_list(access_list);
 		}
 
-		self.state.inc_nonce(caller);
+		if let Err(e) = self.state.try_inc_nonce(caller) {
+			return emit_exit!(e.into(), Vec::new());
+		}
 
-		let context = Context {
-			caller,
-			address,
-			apparent_value: value,
-		};
+		self.transaction_log_start = self.state.log_count();
+		let context = Context::top_level(caller, address, value);
 
 		match self.call_inner(
 			address,
@@ -587,6 +1009,144 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 		}
 	}
 
+	/// Execute a `CALL` transaction like `transact_call`, returning a
+	/// `CallOutcome` instead of the raw exit reason and output.
+	pub fn transact_call_outcome(
+		&mut self,
+		caller: H160,
+		address: H160,
+		value: U256,
+		data: Vec<u8>,
+		gas_limit: u64,
+		access_list: impl Into<AccessList>,
+	) -> CallOutcome {
+		self.transact_call(caller, address, value, data, gas_limit, access_list)
+			.into()
+	}
+
+	/// Apply transactions in order against the shared state. Each transaction
+	/// runs in its own checkpoint, is metered on its own gasometer and starts
+	/// with no accessed addresses or storage slots (EIP-2929). A successful
+	/// transaction is committed, any other is reverted to its checkpoint. The
+	/// executor's gasometer is left untouched.
+	pub fn transact_many(&mut self, txs: &[Transaction]) -> Vec<(ExitReason, Vec<u8>)> {
+		let mut results = Vec::with_capacity(txs.len());
+
+		for tx in txs {
+			let gas_limit = tx.gas_limit();
+			// The checkpoint takes the whole limit and hands back what is left
+			// on exit.
+			let mut gasometer = Gasometer::new(gas_limit, self.config);
+			gasometer
+				.record_unscaled_cost(gas_limit)
+				.expect("A fresh gasometer holds its own limit");
+			let gasometer = mem::replace(&mut self.state.metadata_mut().gasometer, gasometer);
+
+			self.state.metadata_mut().clear_accessed();
+			self.state.enter_checkpoint(gas_limit);
+
+			let (reason, output) = match tx.clone() {
+				Transaction::Call {
+					caller,
+					address,
+					value,
+					data,
+					gas_limit,
+					access_list,
+				} => self.transact_call(caller, address, value, data, gas_limit, access_list),
+				Transaction::Create {
+					caller,
+					value,
+					init_code,
+					gas_limit,
+					access_list,
+				} => self.transact_create(caller, value, init_code, gas_limit, access_list),
+			};
+
+			let kind = match reason {
+				ExitReason::Succeed(_) => StackExitKind::Succeeded,
+				ExitReason::Revert(_) => StackExitKind::Reverted,
+				ExitReason::Error(_) | ExitReason::Fatal(_) => StackExitKind::Failed,
+			};
+			let exited = self.exit_substate(kind);
+			self.state.metadata_mut().gasometer = gasometer;
+
+			match exited {
+				Ok(()) => results.push((reason, output)),
+				Err(e) => results.push((e.into(), Vec::new())),
+			}
+		}
+
+		results
+	}
+
+	/// Run `CREATE` init code in a checkpoint that is always reverted, and
+	/// return the code it would deploy, or its revert data. Unlike
+	/// `transact_create`, no intrinsic cost is charged. The gas limit is
+	/// charged to the executor's gasometer.
+	pub fn simulate_create(
+		&mut self,
+		caller: H160,
+		value: U256,
+		init_code: Vec<u8>,
+		gas_limit: u64,
+	) -> (ExitReason, Vec<u8>) {
+		if let Err(e) = self
+			.state
+			.metadata_mut()
+			.gasometer
+			.record_unscaled_cost(gas_limit)
+		{
+			return (e.into(), Vec::new());
+		}
+
+		self.state.enter_checkpoint(gas_limit);
+
+		let result = match self.create_inner(
+			caller,
+			CreateScheme::Legacy { caller },
+			value,
+			init_code,
+			Some(gas_limit),
+			false,
+		) {
+			Capture::Exit((reason, Some(address), _)) => (reason, self.state.code(address)),
+			Capture::Exit((reason, None, output)) => (reason, output),
+			Capture::Trap(_) => unreachable!(),
+		};
+
+		let _ = self.exit_substate(StackExitKind::Reverted);
+		result
+	}
+
+	/// The block values executed code currently sees.
+	pub fn block_context(&self) -> BlockContext {
+		BlockContext {
+			number: self.block_number(),
+			coinbase: self.block_coinbase(),
+			timestamp: self.block_timestamp(),
+			difficulty: self.block_difficulty(),
+			randomness: self.block_randomness(),
+			gas_limit: self.block_gas_limit(),
+			base_fee_per_gas: self.block_base_fee_per_gas(),
+			blob_base_fee: self.blob_base_fee(),
+			chain_id: self.chain_id(),
+		}
+	}
+
+	/// Run `f` with executed code seeing `block` in place of the backend's
+	/// block values. The previous block values are restored afterwards.
+	pub fn with_block_override<R>(
+		&mut self,
+		block: BlockContext,
+		f: impl FnOnce(&mut Self) -> R,
+	) -> R {
+		let previous = self.block_override.replace(block);
+		let result = f(self);
+		self.block_override = previous;
+		result
+	}
+
 	/// Get used gas for the current executor, given the price.
 	pub fn used_gas(&self) -> u64 {
 		self.state.metadata().gasometer.total_used_gas()
@@ -602,11 +1162,70 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 		U256::from(used_gas).saturating_mul(price)
 	}
 
+	/// Get fee needed for the current executor for an EIP-1559 transaction,
+	/// paying the effective gas price over the current block base fee.
+	pub fn eip1559_fee(&self, max_fee: U256, max_priority_fee: U256) -> U256 {
+		self.fee(gasometer::effective_gas_price(
+			self.block_base_fee_per_gas(),
+			max_fee,
+			max_priority_fee,
+		))
+	}
+
+	/// Balance the caller must hold before a transaction starts: the gas
+	/// limit at the given price plus the transferred value. Saturates at
+	/// `U256::MAX`.
+	pub fn upfront_cost(gas_limit: u64, gas_price: U256, value: U256) -> U256 {
+		U256::from(gas_limit)
+			.saturating_mul(gas_price)
+			.saturating_add(value)
+	}
+
+	/// Storage slots accessed so far, as `(address, slot)` pairs in ascending
+	/// order, e.g. to build an EIP-2930 access list after a call. Slots are
+	/// only tracked when `Config::increase_state_access_gas` is set; the list
+	/// is empty otherwise.
+	pub fn accessed_storage_list(&self) -> Vec<(H160, H256)> {
+		self.state
+			.metadata()
+			.accessed()
+			.as_ref()
+			.map(|accessed| accessed.accessed_storage.iter().copied().collect())
+			.unwrap_or_default()
+	}
+
+	/// Non-zero storage of an account, as currently seen by the executor.
+	/// Only as complete as the backend's `storage_entries`.
+	pub fn storage_snapshot(&self, address: H160) -> BTreeMap<H256, H256> {
+		self.state.storage_entries(address)
+	}
+
+	/// Gas a call requesting `requested` would hand to its callee, given the
+	/// gas left in the current substate: at most all but one 64th of it when
+	/// `Config::call_l64_after_gas` is set (EIP-150), and all of it
+	/// otherwise. `None` requests as much as possible.
+	pub fn forwarded_gas(&self, requested: Option<u64>) -> u64 {
+		let gas = self.state.metadata().gasometer.gas();
+		let after_gas = if self.config.call_l64_after_gas {
+			gas - gas / 64
+		} else {
+			gas
+		};
+
+		min(requested.unwrap_or(after_gas), after_gas)
+	}
+
 	/// Get account nonce.
 	pub fn nonce(&self, address: H160) -> U256 {
 		self.state.basic(address).nonce
 	}
 
+	/// Get the code stored at an account, such as the code deployed by a
+	/// create transaction.
+	pub fn deployed_code(&self, address: H160) -> Vec<u8> {
+		self.state.code(address)
+	}
+
 	/// Get the create address from given scheme.
 	pub fn create_address(&self, scheme: CreateScheme) -> H160 {
 		match scheme {
@@ -618,40 +1237,19 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 				let mut hasher = Keccak256::new();
 				hasher.update(&[0xff]);
 				hasher.update(&caller[..]);
+				if self.config.create2_includes_chain_id {
+					hasher.update(&u256_to_be_bytes(self.state.chain_id())[..]);
+				}
 				hasher.update(&salt[..]);
 				hasher.update(&code_hash[..]);
 				H256::from_slice(hasher.finalize().as_slice()).into()
 			}
 			CreateScheme::Legacy { caller } => {
-				let nonce = self.nonce(caller);
-
-				let nonce_len = (nonce.bits() as u8) / 8 + 1;
-
-				let mut len = 22 + nonce_len;
-				if nonce >= U256::from(128) {
-					len += 1;
-				}
-
-				let mut data = Vec::<u8>::with_capacity(len as usize);
-
-				data.push(192 + len - 1);
-				data.push(148);
-				data.append(&mut caller.0.to_vec());
-
-				if nonce < U256::from(128) {
-					data.push(nonce.byte(0));
-				} else {
-					data.push(128 + nonce_len);
-
-					for i in 0..nonce_len as usize {
-						let b = nonce.byte(i);
-						if b == 0 {
-							data.push(128);
-						} else {
-							data.push(b);
-						}
-					}
-				}
+				let nonce = u256_to_minimal_be_bytes(self.nonce(caller));
+				let data = rlp::encode_list(&[
+					rlp::encode_bytes(caller.as_bytes()),
+					rlp::encode_bytes(&nonce),
+				]);
 
 				H256::from_slice(Keccak256::digest(&data).as_slice()).into()
 			}
@@ -669,20 +1267,79 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 		self.state.metadata_mut().access_storages(storage_keys);
 	}
 
-	fn create_inner(
-		&mut self,
-		caller: H160,
-		scheme: CreateScheme,
-		value: U256,
-		init_code: Vec<u8>,
-		target_gas: Option<u64>,
-		take_l64: bool,
-	) -> Capture<(ExitReason, Option<H160>, Vec<u8>), Infallible> {
-		macro_rules! try_or_fail {
-			( $e:expr ) => {
-				match $e {
-					Ok(v) => v,
-					Err(e) => return Capture::Exit((e.into(), None, Vec::new())),
+	/// Address whose code is read for `address`. With `Config::eip_7702`, an
+	/// account whose code is a delegation indicator resolves to its delegate.
+	fn delegated_address(&self, address: H160) -> H160 {
+		if !self.config.eip_7702 {
+			return address;
+		}
+
+		let code = self.state.code(address);
+		match code.strip_prefix(&DELEGATION_PREFIX[..]) {
+			Some(target) if target.len() == 20 => H160::from_slice(target),
+			_ => address,
+		}
+	}
+
+	/// Whether the code of a self-destructed account is hidden, with
+	/// `Config::hide_deleted_code`.
+	fn is_code_hidden(&self, address: H160) -> bool {
+		self.config.hide_deleted_code && self.state.deleted(address)
+	}
+
+	/// Reject senders with deployed code when `Config::eip_3607` is set. A
+	/// delegation indicator does not count as code.
+	fn check_sender(&self, caller: H160) -> Result<(), ExitError> {
+		if self.config.eip_3607
+			&& self.state.code_size(caller) != U256::zero()
+			&& self.delegated_address(caller) == caller
+		{
+			return Err(ExitError::SenderNotEOA);
+		}
+
+		Ok(())
+	}
+
+	/// Reject callers whose nonce can no longer be increased, before any gas
+	/// is charged or address warmed. See
+	/// [EIP-2681](https://eips.ethereum.org/EIPS/eip-2681).
+	fn check_nonce(&self, caller: H160) -> Result<(), ExitError> {
+		if self.nonce(caller) >= U256::from(u64::MAX) {
+			return Err(ExitError::MaxNonce);
+		}
+
+		Ok(())
+	}
+
+	/// Reject callers that cannot pay for the gas limit and value upfront,
+	/// when `Config::check_upfront_funds` is set.
+	fn check_funds(&self, caller: H160, gas_limit: u64, value: U256) -> Result<(), ExitError> {
+		if !self.config.check_upfront_funds {
+			return Ok(());
+		}
+
+		let cost = Self::upfront_cost(gas_limit, self.state.gas_price(), value);
+		if self.state.basic(caller).balance < cost {
+			return Err(ExitError::OutOfFund);
+		}
+
+		Ok(())
+	}
+
+	fn create_inner(
+		&mut self,
+		caller: H160,
+		scheme: CreateScheme,
+		value: U256,
+		init_code: Vec<u8>,
+		target_gas: Option<u64>,
+		take_l64: bool,
+	) -> Capture<(ExitReason, Option<H160>, Vec<u8>), Infallible> {
+		macro_rules! try_or_fail {
+			( $e:expr ) => {
+				match $e {
+					Ok(v) => v,
+					Err(e) => return Capture::Exit((e.into(), None, Vec::new())),
 				}
 			};
 		}
@@ -723,11 +1380,19 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 			return Capture::Exit((ExitError::OutOfFund.into(), None, Vec::new()));
 		}
 
+		if self.nonce(caller) >= U256::from(u64::MAX) {
+			return Capture::Exit((ExitError::MaxNonce.into(), None, Vec::new()));
+		}
+
 		let after_gas = if take_l64 && self.config.call_l64_after_gas {
 			if self.config.estimate {
 				let initial_after_gas = self.state.metadata().gasometer.gas();
 				let diff = initial_after_gas - l64(initial_after_gas);
-				try_or_fail!(self.state.metadata_mut().gasometer.record_cost(diff));
+				try_or_fail!(self
+					.state
+					.metadata_mut()
+					.gasometer
+					.record_unscaled_cost(diff));
 				self.state.metadata().gasometer.gas()
 			} else {
 				l64(self.state.metadata().gasometer.gas())
@@ -739,19 +1404,18 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 		let target_gas = target_gas.unwrap_or(after_gas);
 
 		let gas_limit = min(after_gas, target_gas);
-		try_or_fail!(self.state.metadata_mut().gasometer.record_cost(gas_limit));
+		try_or_fail!(self
+			.state
+			.metadata_mut()
+			.gasometer
+			.record_unscaled_cost(gas_limit));
 
-		self.state.inc_nonce(caller);
+		try_or_fail!(self.state.try_inc_nonce(caller));
 
 		self.enter_substate(gas_limit, false);
 
 		{
-			if self.code_size(address) != U256::zero() {
-				let _ = self.exit_substate(StackExitKind::Failed);
-				return Capture::Exit((ExitError::CreateCollision.into(), None, Vec::new()));
-			}
-
-			if self.nonce(address) > U256::zero() {
+			if !self.state.is_creatable(address) {
 				let _ = self.exit_substate(StackExitKind::Failed);
 				return Capture::Exit((ExitError::CreateCollision.into(), None, Vec::new()));
 			}
@@ -778,7 +1442,10 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 		}
 
 		if self.config.create_increase_nonce {
-			self.state.inc_nonce(address);
+			if let Err(e) = self.state.try_inc_nonce(address) {
+				let _ = self.exit_substate(StackExitKind::Reverted);
+				return Capture::Exit((ExitReason::Error(e), None, Vec::new()));
+			}
 		}
 
 		let mut runtime = Runtime::new(
@@ -788,7 +1455,9 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 			self.config,
 		);
 
+		self.call_stack.push(address);
 		let reason = self.execute(&mut runtime);
+		self.call_stack.pop();
 		log::debug!(target: "evm", "Create execution using address {}: {:?}", address, reason);
 
 		match reason {
@@ -838,12 +1507,15 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 				Capture::Exit((ExitReason::Error(e), None, Vec::new()))
 			}
 			ExitReason::Revert(e) => {
+				let out = runtime.machine().return_value();
+				if matches!(self.config.max_return_data_size, Some(limit) if out.len() > limit) {
+					self.state.metadata_mut().gasometer.fail();
+					let _ = self.exit_substate(StackExitKind::Failed);
+					return Capture::Exit((ExitError::ReturnDataTooLarge.into(), None, Vec::new()));
+				}
+
 				let _ = self.exit_substate(StackExitKind::Reverted);
-				Capture::Exit((
-					ExitReason::Revert(e),
-					None,
-					runtime.machine().return_value(),
-				))
+				Capture::Exit((ExitReason::Revert(e), None, out))
 			}
 			ExitReason::Fatal(e) => {
 				self.state.metadata_mut().gasometer.fail();
@@ -887,11 +1559,21 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 			context: &context,
 		});
 
+		if let Some(max_call_data_size) = self.config.max_call_data_size {
+			if input.len() > max_call_data_size {
+				return Capture::Exit((ExitError::CallDataTooLarge.into(), Vec::new()));
+			}
+		}
+
 		let after_gas = if take_l64 && self.config.call_l64_after_gas {
 			if self.config.estimate {
 				let initial_after_gas = self.state.metadata().gasometer.gas();
 				let diff = initial_after_gas - l64(initial_after_gas);
-				try_or_fail!(self.state.metadata_mut().gasometer.record_cost(diff));
+				try_or_fail!(self
+					.state
+					.metadata_mut()
+					.gasometer
+					.record_unscaled_cost(diff));
 				self.state.metadata().gasometer.gas()
 			} else {
 				l64(self.state.metadata().gasometer.gas())
@@ -903,7 +1585,11 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 		let target_gas = target_gas.unwrap_or(after_gas);
 		let mut gas_limit = min(target_gas, after_gas);
 
-		try_or_fail!(self.state.metadata_mut().gasometer.record_cost(gas_limit));
+		try_or_fail!(self
+			.state
+			.metadata_mut()
+			.gasometer
+			.record_unscaled_cost(gas_limit));
 
 		if let Some(transfer) = transfer.as_ref() {
 			if take_stipend && transfer.value != U256::zero() {
@@ -923,6 +1609,17 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 			}
 		}
 
+		if !self.config.precompiles_accept_value
+			&& matches!(&transfer, Some(transfer) if transfer.value != U256::zero())
+			&& self.precompile_set.is_precompile(code_address)
+		{
+			let _ = self.exit_substate(StackExitKind::Reverted);
+			return Capture::Exit((
+				ExitError::Other("precompile does not accept value".into()).into(),
+				Vec::new(),
+			));
+		}
+
 		if let Some(transfer) = transfer {
 			match self.state.transfer(transfer) {
 				Ok(()) => (),
@@ -972,15 +1669,38 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 			};
 		}
 
+		// Nothing to execute, only the transfer above applies.
+		if code.is_empty() {
+			let _ = self.exit_substate(StackExitKind::Succeeded);
+			return Capture::Exit((ExitSucceed::Stopped.into(), Vec::new()));
+		}
+
 		let mut runtime = Runtime::new(Rc::new(code), Rc::new(input), context, self.config);
 
+		self.call_stack.push(code_address);
 		let reason = self.execute(&mut runtime);
+		self.call_stack.pop();
 		log::debug!(target: "evm", "Call execution using address {}: {:?}", code_address, reason);
 
+		let output = runtime.machine().return_value();
+		let reason = match self.config.max_return_data_size {
+			Some(max_return_data_size)
+				if output.len() > max_return_data_size
+					&& (reason.is_succeed() || reason.is_revert()) =>
+			{
+				ExitError::ReturnDataTooLarge.into()
+			}
+			_ => reason,
+		};
+
 		match reason {
 			ExitReason::Succeed(s) => {
 				let _ = self.exit_substate(StackExitKind::Succeeded);
-				Capture::Exit((ExitReason::Succeed(s), runtime.machine().return_value()))
+				Capture::Exit((ExitReason::Succeed(s), output))
+			}
+			ExitReason::Error(ExitError::InvalidJump) if !self.config.bad_jump_consumes_all_gas => {
+				let _ = self.exit_substate(StackExitKind::Reverted);
+				Capture::Exit((ExitError::InvalidJump.into(), Vec::new()))
 			}
 			ExitReason::Error(e) => {
 				let _ = self.exit_substate(StackExitKind::Failed);
@@ -988,7 +1708,7 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 			}
 			ExitReason::Revert(e) => {
 				let _ = self.exit_substate(StackExitKind::Reverted);
-				Capture::Exit((ExitReason::Revert(e), runtime.machine().return_value()))
+				Capture::Exit((ExitReason::Revert(e), output))
 			}
 			ExitReason::Fatal(e) => {
 				self.state.metadata_mut().gasometer.fail();
@@ -999,6 +1719,23 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet>
 	}
 }
 
+impl<'config, 'precompiles, S: StackState<'config> + Clone, P: PrecompileSet>
+	StackExecutor<'config, 'precompiles, S, P>
+{
+	/// Fork the executor into an independent one, with a cloned state and
+	/// substate metadata, sharing the config and precompiles.
+	pub fn fork(&self) -> Self {
+		Self {
+			config: self.config,
+			state: self.state.clone(),
+			precompile_set: self.precompile_set,
+			call_stack: self.call_stack.clone(),
+			block_override: self.block_override.clone(),
+			transaction_log_start: self.transaction_log_start,
+		}
+	}
+}
+
 impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet> Handler
 	for StackExecutor<'config, 'precompiles, S, P>
 {
@@ -1012,19 +1749,30 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet> Handler
 	}
 
 	fn code_size(&self, address: H160) -> U256 {
-		self.state.code_size(address)
+		if self.is_code_hidden(address) {
+			return U256::zero();
+		}
+
+		self.state.code_size(self.delegated_address(address))
 	}
 
 	fn code_hash(&self, address: H160) -> H256 {
 		if !self.exists(address) {
 			return H256::default();
 		}
+		if self.is_code_hidden(address) {
+			return H256::from_slice(Keccak256::digest(b"").as_slice());
+		}
 
-		self.state.code_hash(address)
+		self.state.code_hash(self.delegated_address(address))
 	}
 
 	fn code(&self, address: H160) -> Vec<u8> {
-		self.state.code(address)
+		if self.is_code_hidden(address) {
+			return Vec::new();
+		}
+
+		self.state.code(self.delegated_address(address))
 	}
 
 	fn storage(&self, address: H160, index: H256) -> H256 {
@@ -1063,28 +1811,70 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet> Handler
 		self.state.origin()
 	}
 	fn block_hash(&self, number: U256) -> H256 {
-		self.state.block_hash(number)
+		match &self.block_override {
+			// Only the 256 blocks before the overridden one are visible.
+			Some(block) if number >= block.number || block.number - number > U256::from(256) => {
+				H256::default()
+			}
+			_ => self.state.block_hash(number),
+		}
 	}
 	fn block_number(&self) -> U256 {
-		self.state.block_number()
+		match &self.block_override {
+			Some(block) => block.number,
+			None => self.state.block_number(),
+		}
 	}
 	fn block_coinbase(&self) -> H160 {
-		self.state.block_coinbase()
+		match &self.block_override {
+			Some(block) => block.coinbase,
+			None => self.state.block_coinbase(),
+		}
 	}
 	fn block_timestamp(&self) -> U256 {
-		self.state.block_timestamp()
+		match &self.block_override {
+			Some(block) => block.timestamp,
+			None => self.state.block_timestamp(),
+		}
 	}
 	fn block_difficulty(&self) -> U256 {
-		self.state.block_difficulty()
+		match &self.block_override {
+			Some(block) => block.difficulty,
+			None => self.state.block_difficulty(),
+		}
+	}
+	fn block_randomness(&self) -> Option<H256> {
+		match &self.block_override {
+			Some(block) => block.randomness,
+			None => self.state.block_randomness(),
+		}
 	}
 	fn block_gas_limit(&self) -> U256 {
-		self.state.block_gas_limit()
+		match &self.block_override {
+			Some(block) => block.gas_limit,
+			None => self.state.block_gas_limit(),
+		}
 	}
 	fn block_base_fee_per_gas(&self) -> U256 {
-		self.state.block_base_fee_per_gas()
+		match &self.block_override {
+			Some(block) => block.base_fee_per_gas,
+			None => self.state.block_base_fee_per_gas(),
+		}
+	}
+	fn blob_base_fee(&self) -> U256 {
+		match &self.block_override {
+			Some(block) => block.blob_base_fee,
+			None => self.state.blob_base_fee(),
+		}
+	}
+	fn blob_hash(&self, index: U256) -> H256 {
+		self.state.blob_hash(index)
 	}
 	fn chain_id(&self) -> U256 {
-		self.state.chain_id()
+		match &self.block_override {
+			Some(block) => block.chain_id,
+			None => self.state.chain_id(),
+		}
 	}
 
 	fn deleted(&self, address: H160) -> bool {
@@ -1097,6 +1887,16 @@ impl<'config, 'precompiles, S: StackState<'config>, P: PrecompileSet> Handler
 	}
 
 	fn log(&mut self, address: H160, topics: Vec<H256>, data: Vec<u8>) -> Result<(), ExitError> {
+		if let Some(limit) = self.config.max_logs {
+			let count = self
+				.state
+				.log_count()
+				.saturating_sub(self.transaction_log_start);
+			if count >= limit {
+				return Err(ExitError::Other("log limit".into()));
+			}
+		}
+
 		self.state.log(address, topics, data);
 		Ok(())
 	}
@@ -1331,6 +2131,10 @@ impl<'inner, 'config, 'precompiles, S: StackState<'config>, P: PrecompileSet> Pr
 
 	/// Record a log.
 	fn log(&mut self, address: H160, topics: Vec<H256>, data: Vec<u8>) -> Result<(), ExitError> {
+		if self.is_static {
+			return Err(ExitError::Other("log in static call".into()));
+		}
+
 		Handler::log(self.executor, address, topics, data)
 	}
 
@@ -1358,4 +2162,1951 @@ impl<'inner, 'config, 'precompiles, S: StackState<'config>, P: PrecompileSet> Pr
 	fn gas_limit(&self) -> Option<u64> {
 		self.gas_limit
 	}
+
+	/// Whether `address` is already executing in an enclosing frame.
+	fn is_reentrant(&self, address: H160) -> bool {
+		self.executor.is_reentrant(address)
+	}
+
+	/// Depth of the call frame running the precompile.
+	fn depth(&self) -> usize {
+		self.executor.depth()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{
+		storage_diff, AccessList, AccessListItem, BaseCostPrecompileSet, BlockContext, CallOutcome,
+		PrecompileFailure, PrecompileFn, PrecompileHandle, PrecompileOutput, PrecompileResult,
+		PrecompileSet, StackExecutor, StackSubstateMetadata, Transaction,
+	};
+	use crate::backend::{Backend, Log, MemoryAccount, MemoryBackend, MemoryVicinity};
+	use crate::executor::stack::{MemoryStackState, StackState};
+	use crate::test_utils::{
+		call, vicinity, with_executor, with_executor_and_gas_limit, with_executor_and_precompiles,
+		Executor, CALLER, CONTRACT, GAS_LIMIT,
+	};
+	use crate::{
+		Config, Context, CreateScheme, ExitError, ExitFatal, ExitReason, ExitRevert, ExitSucceed,
+		Handler, Runtime,
+	};
+	use alloc::collections::BTreeMap;
+	use alloc::rc::Rc;
+	use core::cell::RefCell;
+	use core::sync::atomic::{AtomicBool, Ordering};
+	use primitive_types::{H160, H256, U256};
+	use sha3::{Digest, Keccak256};
+
+	/// A precompile that stops without output, for free.
+	fn noop_precompile(
+		_: &[u8],
+		_: Option<u64>,
+		_: &Context,
+		_: bool,
+	) -> Result<(PrecompileOutput, u64), PrecompileFailure> {
+		Ok((
+			PrecompileOutput {
+				exit_status: ExitSucceed::Stopped,
+				output: Vec::new(),
+			},
+			0,
+		))
+	}
+
+	// PUSH1 0, CALLDATALOAD, PUSH1 0, RETURN: returns as many bytes as the
+	// first call data word says.
+	const RETURN_N_BYTES: [u8; 6] = [0x60, 0x00, 0x35, 0x60, 0x00, 0xf3];
+
+	fn word(n: usize) -> Vec<u8> {
+		H256::from_low_u64_be(n as u64).as_bytes().to_vec()
+	}
+
+	#[test]
+	fn max_call_data_size() {
+		let mut config = Config::istanbul();
+		config.max_call_data_size = Some(32);
+
+		with_executor(&config, RETURN_N_BYTES.to_vec(), |executor| {
+			assert_eq!(call(executor, word(0)).0, ExitSucceed::Returned.into());
+
+			let mut input = word(0);
+			input.push(0);
+			assert_eq!(call(executor, input).0, ExitError::CallDataTooLarge.into());
+		});
+	}
+
+	#[test]
+	fn max_return_data_size() {
+		let mut config = Config::istanbul();
+		config.max_return_data_size = Some(64);
+
+		with_executor(&config, RETURN_N_BYTES.to_vec(), |executor| {
+			let (reason, output) = call(executor, word(64));
+			assert_eq!(reason, ExitSucceed::Returned.into());
+			assert_eq!(output.len(), 64);
+
+			let (reason, output) = call(executor, word(65));
+			assert_eq!(reason, ExitError::ReturnDataTooLarge.into());
+			assert!(output.is_empty());
+		});
+	}
+
+	#[test]
+	fn revert_data_is_bounded() {
+		// PUSH4 0xffffffff, PUSH1 0, REVERT
+		let code = vec![0x63, 0xff, 0xff, 0xff, 0xff, 0x60, 0x00, 0xfd];
+		with_executor(&Config::istanbul(), code, |executor| {
+			let (reason, output) = call(executor, Vec::new());
+			assert_eq!(reason, ExitError::OutOfGas.into());
+			assert!(output.is_empty());
+		});
+
+		let mut config = Config::istanbul();
+		config.max_return_data_size = Some(64);
+		// PUSH1 len, PUSH1 0, REVERT
+		let init_code = |len: u8| vec![0x60, len, 0x60, 0x00, 0xfd];
+		with_executor(&config, Vec::new(), |executor| {
+			let (reason, output) = executor.transact_create(
+				CALLER,
+				U256::zero(),
+				init_code(64),
+				GAS_LIMIT,
+				Vec::new(),
+			);
+			assert_eq!(reason, ExitRevert::Reverted.into());
+			assert_eq!(output.len(), 64);
+
+			let (reason, output) = executor.transact_create(
+				CALLER,
+				U256::zero(),
+				init_code(65),
+				GAS_LIMIT,
+				Vec::new(),
+			);
+			assert_eq!(reason, ExitError::ReturnDataTooLarge.into());
+			assert!(output.is_empty());
+		});
+	}
+
+	#[test]
+	fn call_outcome_from_exit_reason() {
+		let output = vec![1, 2, 3];
+		let outcome = |reason: ExitReason| CallOutcome::from((reason, output.clone()));
+
+		assert_eq!(
+			outcome(ExitSucceed::Returned.into()),
+			CallOutcome::Success(output.clone())
+		);
+		assert_eq!(
+			outcome(ExitRevert::Reverted.into()),
+			CallOutcome::Revert(output.clone())
+		);
+		assert_eq!(
+			outcome(ExitError::OutOfGas.into()),
+			CallOutcome::Error(ExitError::OutOfGas)
+		);
+		assert_eq!(
+			outcome(ExitFatal::NotSupported.into()),
+			CallOutcome::Fatal(ExitFatal::NotSupported)
+		);
+	}
+
+	#[test]
+	fn transact_call_outcome() {
+		with_executor(&Config::istanbul(), RETURN_N_BYTES.to_vec(), |executor| {
+			let outcome = executor.transact_call_outcome(
+				CALLER,
+				CONTRACT,
+				U256::zero(),
+				word(2),
+				1_000_000,
+				Vec::new(),
+			);
+			assert_eq!(outcome, CallOutcome::Success(vec![0, 0]));
+		});
+	}
+
+	#[test]
+	fn gas_multiplier_scales_execution_gas() {
+		// PUSH1 1, PUSH1 0, MSTORE, STOP
+		let code = vec![0x60, 0x01, 0x60, 0x00, 0x52, 0x00];
+		let execution_gas = |gas_multiplier| {
+			let mut config = Config::istanbul();
+			config.gas_multiplier = gas_multiplier;
+			with_executor(&config, code.clone(), |executor| {
+				assert_eq!(call(executor, Vec::new()).0, ExitSucceed::Stopped.into());
+				executor.used_gas() - 21_000
+			})
+		};
+
+		assert_eq!(execution_gas(1), 12);
+		assert_eq!(execution_gas(2), 24);
+	}
+
+	#[test]
+	fn difficulty_and_prevrandao() {
+		// DIFFICULTY, PUSH1 0, MSTORE, PUSH1 32, PUSH1 0, RETURN
+		let code = vec![0x44, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3];
+
+		let output = with_executor(&Config::london(), code.clone(), |executor| {
+			call(executor, Vec::new()).1
+		});
+		assert_eq!(output, word(0x0d));
+
+		let output = with_executor(&Config::merge(), code, |executor| {
+			call(executor, Vec::new()).1
+		});
+		assert_eq!(output, H256::repeat_byte(0x5a).as_bytes());
+	}
+
+	#[test]
+	fn precompile_base_cost_checked_up_front() {
+		static EXECUTED: AtomicBool = AtomicBool::new(false);
+
+		fn identity(
+			input: &[u8],
+			_: Option<u64>,
+			_: &Context,
+			_: bool,
+		) -> Result<(PrecompileOutput, u64), PrecompileFailure> {
+			EXECUTED.store(true, Ordering::SeqCst);
+			Ok((
+				PrecompileOutput {
+					exit_status: ExitSucceed::Returned,
+					output: input.to_vec(),
+				},
+				15,
+			))
+		}
+
+		let config = Config::istanbul();
+		let vicinity = vicinity();
+		let backend = MemoryBackend::new(&vicinity, BTreeMap::new());
+		let identity_address = H160::from_low_u64_be(4);
+		let mut precompiles = BaseCostPrecompileSet::default();
+		precompiles
+			.0
+			.insert(identity_address, (identity as PrecompileFn, 15));
+		let call_identity = |gas_limit| {
+			let metadata = StackSubstateMetadata::new(gas_limit, &config);
+			let state = MemoryStackState::new(metadata, &backend);
+			let mut executor = StackExecutor::new_with_precompiles(state, &config, &precompiles);
+			executor.transact_call(
+				CALLER,
+				identity_address,
+				U256::zero(),
+				vec![1, 2, 3],
+				gas_limit,
+				Vec::new(),
+			)
+		};
+
+		// Intrinsic cost is 21000 plus 16 per non-zero byte.
+		let intrinsic = 21_000 + 3 * 16;
+		assert_eq!(call_identity(intrinsic + 10).0, ExitError::OutOfGas.into());
+		assert!(!EXECUTED.load(Ordering::SeqCst));
+
+		assert_eq!(
+			call_identity(intrinsic + 15),
+			(ExitSucceed::Returned.into(), vec![1, 2, 3])
+		);
+		assert!(EXECUTED.load(Ordering::SeqCst));
+	}
+
+	#[test]
+	fn value_transfer_to_precompile() {
+		fn identity(
+			input: &[u8],
+			_: Option<u64>,
+			_: &Context,
+			_: bool,
+		) -> Result<(PrecompileOutput, u64), PrecompileFailure> {
+			Ok((
+				PrecompileOutput {
+					exit_status: ExitSucceed::Returned,
+					output: input.to_vec(),
+				},
+				15,
+			))
+		}
+
+		let vicinity = vicinity();
+		let mut accounts = BTreeMap::new();
+		accounts.insert(
+			CALLER,
+			MemoryAccount {
+				balance: U256::from(10_000_000),
+				..Default::default()
+			},
+		);
+		let backend = MemoryBackend::new(&vicinity, accounts);
+		let identity_address = H160::from_low_u64_be(4);
+		let mut precompiles = BTreeMap::new();
+		precompiles.insert(identity_address, identity as PrecompileFn);
+		let call_identity = |config: &Config| {
+			let metadata = StackSubstateMetadata::new(GAS_LIMIT, config);
+			let state = MemoryStackState::new(metadata, &backend);
+			let mut executor = StackExecutor::new_with_precompiles(state, config, &precompiles);
+			let result = executor.transact_call(
+				CALLER,
+				identity_address,
+				U256::from(5),
+				vec![1, 2, 3],
+				GAS_LIMIT,
+				Vec::new(),
+			);
+			(result, executor.balance(identity_address))
+		};
+
+		assert_eq!(
+			call_identity(&Config::istanbul()),
+			((ExitSucceed::Returned.into(), vec![1, 2, 3]), U256::from(5))
+		);
+
+		let config = Config {
+			precompiles_accept_value: false,
+			..Config::istanbul()
+		};
+		assert_eq!(
+			call_identity(&config),
+			(
+				(
+					ExitError::Other("precompile does not accept value".into()).into(),
+					Vec::new()
+				),
+				U256::zero()
+			)
+		);
+	}
+
+	#[test]
+	fn custom_precompile_is_warm() {
+		let mut precompiles = BTreeMap::new();
+		precompiles.insert(
+			H160::from_low_u64_be(0x1234),
+			noop_precompile as PrecompileFn,
+		);
+		let balance_gas = |target: u16| {
+			let [hi, lo] = target.to_be_bytes();
+			// PUSH2 target, BALANCE, STOP
+			let code = vec![0x61, hi, lo, 0x31, 0x00];
+			with_executor_and_precompiles(
+				&Config::berlin(),
+				code,
+				GAS_LIMIT,
+				&precompiles,
+				|executor| {
+					assert_eq!(call(executor, Vec::new()).0, ExitSucceed::Stopped.into());
+					executor.used_gas()
+				},
+			)
+		};
+
+		assert_eq!(balance_gas(0x1234), 21_000 + 3 + 100);
+		assert_eq!(balance_gas(0x1235), 21_000 + 3 + 2600);
+	}
+
+	#[test]
+	fn fork_is_independent() {
+		with_executor(&Config::istanbul(), Vec::new(), |executor| {
+			let key = H256::repeat_byte(0x01);
+			let mut fork = executor.fork();
+			fork.state_mut()
+				.set_storage(CONTRACT, key, H256::repeat_byte(0x02));
+
+			assert_eq!(fork.state().storage(CONTRACT, key), H256::repeat_byte(0x02));
+			assert_eq!(executor.state().storage(CONTRACT, key), H256::default());
+		});
+	}
+
+	const DELEGATE: H160 = H160::repeat_byte(0x03);
+
+	fn delegation_indicator(address: H160) -> Vec<u8> {
+		let mut code = vec![0xef, 0x01, 0x00];
+		code.extend_from_slice(address.as_bytes());
+		code
+	}
+
+	#[test]
+	fn eip_7702_delegated_code() {
+		let mut config = Config::istanbul();
+		config.eip_7702 = true;
+
+		with_executor(&config, delegation_indicator(DELEGATE), |executor| {
+			executor
+				.state_mut()
+				.set_code(DELEGATE, RETURN_N_BYTES.to_vec());
+
+			assert_eq!(executor.code(CONTRACT), RETURN_N_BYTES.to_vec());
+			assert_eq!(executor.code_hash(CONTRACT), executor.code_hash(DELEGATE));
+			assert_eq!(
+				call(executor, word(32)),
+				(ExitSucceed::Returned.into(), vec![0; 32])
+			);
+		});
+	}
+
+	#[test]
+	fn eip_7702_non_delegated_code() {
+		let mut config = Config::istanbul();
+		config.eip_7702 = true;
+
+		with_executor(&config, RETURN_N_BYTES.to_vec(), |executor| {
+			assert_eq!(executor.code(CONTRACT), RETURN_N_BYTES.to_vec());
+		});
+
+		let indicator = delegation_indicator(DELEGATE);
+		with_executor(&Config::istanbul(), indicator.clone(), |executor| {
+			assert_eq!(executor.code(CONTRACT), indicator);
+		});
+	}
+
+	#[test]
+	fn eip_7702_ext_code_opcodes() {
+		const OBSERVER: H160 = H160::repeat_byte(0x04);
+		let mut push_contract = vec![0x73];
+		push_contract.extend_from_slice(CONTRACT.as_bytes());
+		// EXTCODESIZE(CONTRACT), PUSH1 0, MSTORE, EXTCODEHASH(CONTRACT),
+		// PUSH1 32, MSTORE, EXTCODECOPY(CONTRACT, 64, 0, 6), RETURN(0, 96)
+		let code = [
+			&push_contract[..],
+			&[0x3b, 0x60, 0x00, 0x52][..],
+			&push_contract[..],
+			&[0x3f, 0x60, 0x20, 0x52, 0x60, 0x06, 0x60, 0x00, 0x60, 0x40][..],
+			&push_contract[..],
+			&[0x3c, 0x60, 0x60, 0x60, 0x00, 0xf3][..],
+		]
+		.concat();
+		let mut config = Config::istanbul();
+		config.eip_7702 = true;
+
+		with_executor(&config, delegation_indicator(DELEGATE), |executor| {
+			executor
+				.state_mut()
+				.set_code(DELEGATE, RETURN_N_BYTES.to_vec());
+			executor.state_mut().set_code(OBSERVER, code);
+
+			let (reason, output) = executor.transact_call(
+				CALLER,
+				OBSERVER,
+				U256::zero(),
+				Vec::new(),
+				GAS_LIMIT,
+				Vec::new(),
+			);
+			assert_eq!(reason, ExitSucceed::Returned.into());
+			let mut copied = RETURN_N_BYTES.to_vec();
+			copied.resize(32, 0);
+			assert_eq!(
+				output,
+				[
+					&word(RETURN_N_BYTES.len())[..],
+					&Keccak256::digest(RETURN_N_BYTES)[..],
+					&copied[..],
+				]
+				.concat()
+			);
+		});
+	}
+
+	#[test]
+	fn bad_jump_gas() {
+		// PUSH1 0x05, JUMP
+		let code = vec![0x60, 0x05, 0x56];
+		let used_gas = |bad_jump_consumes_all_gas| {
+			let mut config = Config::istanbul();
+			config.bad_jump_consumes_all_gas = bad_jump_consumes_all_gas;
+			with_executor(&config, code.clone(), |executor| {
+				assert_eq!(call(executor, Vec::new()).0, ExitError::InvalidJump.into());
+				executor.used_gas()
+			})
+		};
+
+		assert_eq!(used_gas(true), GAS_LIMIT);
+		assert_eq!(used_gas(false), 21_000 + 3 + 8);
+	}
+
+	#[test]
+	fn configured_stack_limit() {
+		let mut config = Config::istanbul();
+		config.stack_limit = 16;
+
+		with_executor(&config, [0x60, 0x00].repeat(16), |executor| {
+			assert_eq!(call(executor, Vec::new()).0, ExitSucceed::Stopped.into());
+		});
+		with_executor(&config, [0x60, 0x00].repeat(17), |executor| {
+			assert_eq!(
+				call(executor, Vec::new()).0,
+				ExitError::StackOverflow.into()
+			);
+		});
+	}
+
+	#[test]
+	fn jumpdest_gas() {
+		assert_eq!(
+			crate::gasometer::static_opcode_cost(crate::Opcode::JUMPDEST),
+			Some(1)
+		);
+
+		with_executor(&Config::istanbul(), vec![0x5b; 10], |executor| {
+			assert_eq!(call(executor, Vec::new()).0, ExitSucceed::Stopped.into());
+			assert_eq!(executor.used_gas(), 21_000 + 10);
+		});
+	}
+
+	#[test]
+	fn transact_many_commits_successful_transactions() {
+		// PUSH1 0, SLOAD, PUSH1 1, ADD, DUP1, PUSH1 0, SSTORE, PUSH1 0, MSTORE,
+		// PUSH1 32, PUSH1 0, RETURN: increments and returns a counter.
+		let code = vec![
+			0x60, 0x00, 0x54, 0x60, 0x01, 0x01, 0x80, 0x60, 0x00, 0x55, 0x60, 0x00, 0x52, 0x60,
+			0x20, 0x60, 0x00, 0xf3,
+		];
+		let increment = |gas_limit| Transaction::Call {
+			caller: CALLER,
+			address: CONTRACT,
+			value: U256::zero(),
+			data: Vec::new(),
+			gas_limit,
+			access_list: Vec::new(),
+		};
+
+		with_executor(&Config::istanbul(), code, |executor| {
+			let results = executor.transact_many(&[
+				increment(100_000),
+				increment(21_100),
+				increment(100_000),
+			]);
+
+			assert_eq!(results[0], (ExitSucceed::Returned.into(), word(1)));
+			assert_eq!(results[1], (ExitError::OutOfGas.into(), Vec::new()));
+			assert_eq!(results[2], (ExitSucceed::Returned.into(), word(2)));
+			assert_eq!(
+				executor.state().storage(CONTRACT, H256::default()),
+				H256::from_low_u64_be(2)
+			);
+		});
+	}
+
+	#[test]
+	fn transact_many_isolates_transactions() {
+		// Each limit is most of the executor's, so together they exceed it.
+		let gas_limit = GAS_LIMIT * 3 / 4;
+		// PUSH1 0, SLOAD, POP, GAS, PUSH1 0, MSTORE, PUSH1 32, PUSH1 0, RETURN:
+		// returns the gas left after a SLOAD.
+		let sload = Transaction::Call {
+			caller: CALLER,
+			address: CONTRACT,
+			value: U256::zero(),
+			data: Vec::new(),
+			gas_limit,
+			access_list: Vec::new(),
+		};
+		// PUSH1 0, PUSH1 0, REVERT
+		let revert = Transaction::Create {
+			caller: CALLER,
+			value: U256::zero(),
+			init_code: vec![0x60, 0x00, 0x60, 0x00, 0xfd],
+			gas_limit,
+			access_list: Vec::new(),
+		};
+
+		with_executor(
+			&Config::berlin(),
+			vec![
+				0x60, 0x00, 0x54, 0x50, 0x5a, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3,
+			],
+			|executor| {
+				let results = executor.transact_many(&[sload.clone(), sload.clone(), sload]);
+				// Every transaction pays for a cold SLOAD, then POP and GAS.
+				let cold_sload = 21_000 + 3 + 2100;
+				let gas_left = word(gas_limit as usize - cold_sload - 2 - 2);
+				for result in &results {
+					assert_eq!(result, &(ExitSucceed::Returned.into(), gas_left.clone()));
+				}
+				assert_eq!(executor.nonce(CALLER), U256::from(3));
+				assert_eq!(executor.used_gas(), 0);
+				assert_eq!(executor.depth(), 0);
+
+				// A reverted transaction leaves no trace, not even its nonce bump.
+				let results = executor.transact_many(&[revert]);
+				assert_eq!(results[0].0, ExitRevert::Reverted.into());
+				assert_eq!(executor.nonce(CALLER), U256::from(3));
+				assert_eq!(executor.used_gas(), 0);
+			},
+		);
+	}
+
+	#[test]
+	fn eip_3607_rejects_contract_senders() {
+		let mut config = Config::istanbul();
+		config.eip_3607 = true;
+
+		with_executor(&config, vec![0x00], |executor| {
+			assert_eq!(call(executor, Vec::new()).0, ExitSucceed::Stopped.into());
+			assert_eq!(
+				executor
+					.transact_call(
+						CONTRACT,
+						CALLER,
+						U256::zero(),
+						Vec::new(),
+						GAS_LIMIT,
+						Vec::new()
+					)
+					.0,
+				ExitError::SenderNotEOA.into()
+			);
+			assert_eq!(
+				executor
+					.transact_create(CONTRACT, U256::zero(), Vec::new(), GAS_LIMIT, Vec::new())
+					.0,
+				ExitError::SenderNotEOA.into()
+			);
+		});
+	}
+
+	#[test]
+	fn blob_opcodes() {
+		// PUSH1 1, BLOBHASH, PUSH1 0, MSTORE, PUSH1 2, BLOBHASH, PUSH1 32, MSTORE,
+		// BLOBBASEFEE, PUSH1 64, MSTORE, PUSH1 96, PUSH1 0, RETURN
+		let code = vec![
+			0x60, 0x01, 0x49, 0x60, 0x00, 0x52, 0x60, 0x02, 0x49, 0x60, 0x20, 0x52, 0x4a, 0x60,
+			0x40, 0x52, 0x60, 0x60, 0x60, 0x00, 0xf3,
+		];
+		let mut config = Config::istanbul();
+		config.has_blob_opcodes = true;
+
+		with_executor(&config, code.clone(), |executor| {
+			let mut expected = H256::repeat_byte(0xb1).as_bytes().to_vec();
+			expected.extend_from_slice(&[0; 32]);
+			expected.extend_from_slice(&word(7));
+			assert_eq!(
+				call(executor, Vec::new()),
+				(ExitSucceed::Returned.into(), expected)
+			);
+		});
+		with_executor(&Config::istanbul(), code, |executor| {
+			assert_eq!(
+				call(executor, Vec::new()).0,
+				ExitError::InvalidCode(crate::Opcode::BLOBHASH).into()
+			);
+		});
+	}
+
+	#[test]
+	fn refunds_of_reverted_subcalls_are_discarded() {
+		const CHILD: H160 = H160::repeat_byte(0x04);
+		// CALL(GAS, CHILD, 0, 0, 0, 0, 0), STOP
+		let mut code = [0x60, 0x00].repeat(5);
+		code.push(0x73);
+		code.extend_from_slice(CHILD.as_bytes());
+		code.extend_from_slice(&[0x5a, 0xf1, 0x00]);
+
+		let refunded_gas = |child_code: Vec<u8>| {
+			with_executor(&Config::istanbul(), code.clone(), |executor| {
+				executor.state_mut().set_code(CHILD, child_code);
+				executor
+					.state_mut()
+					.set_storage(CHILD, H256::default(), H256::repeat_byte(0x01));
+
+				assert_eq!(call(executor, Vec::new()).0, ExitSucceed::Stopped.into());
+				executor.state().metadata().gasometer().refunded_gas()
+			})
+		};
+
+		// PUSH1 0, PUSH1 0, SSTORE, then STOP or PUSH1 0, PUSH1 0, REVERT
+		let clear_slot = vec![0x60, 0x00, 0x60, 0x00, 0x55];
+		let mut stop = clear_slot.clone();
+		stop.push(0x00);
+		let mut revert = clear_slot;
+		revert.extend_from_slice(&[0x60, 0x00, 0x60, 0x00, 0xfd]);
+
+		assert!(refunded_gas(stop) > 0);
+		assert_eq!(refunded_gas(revert), 0);
+	}
+
+	#[test]
+	fn touched_accounts_of_transfer_and_sstore() {
+		// PUSH1 1, PUSH1 0, SSTORE, STOP
+		let code = vec![0x60, 0x01, 0x60, 0x00, 0x55, 0x00];
+
+		with_executor(&Config::istanbul(), code, |executor| {
+			assert!(executor.state().touched_accounts().is_empty());
+			assert_eq!(
+				executor
+					.transact_call(
+						CALLER,
+						CONTRACT,
+						U256::one(),
+						Vec::new(),
+						GAS_LIMIT,
+						Vec::new()
+					)
+					.0,
+				ExitSucceed::Stopped.into()
+			);
+			assert_eq!(
+				executor.state().touched_accounts(),
+				vec![CALLER, CONTRACT].into_iter().collect()
+			);
+		});
+	}
+
+	#[test]
+	fn touched_accounts_skip_zero_value_call() {
+		const OTHER: H160 = H160::repeat_byte(0x03);
+
+		// CALL(GAS, OTHER, 0, 0, 0, 0, 0), POP, STOP
+		let mut code = [0x60, 0x00].repeat(5);
+		code.push(0x73);
+		code.extend_from_slice(OTHER.as_bytes());
+		code.extend_from_slice(&[0x5a, 0xf1, 0x50, 0x00]);
+
+		with_executor(&Config::istanbul(), code, |executor| {
+			assert_eq!(call(executor, Vec::new()).0, ExitSucceed::Stopped.into());
+			// Only the caller nonce changed.
+			assert_eq!(
+				executor.state().touched_accounts(),
+				vec![CALLER].into_iter().collect()
+			);
+		});
+	}
+
+	#[test]
+	fn disable_gas_metering() {
+		// PUSH3 50000, JUMPDEST, PUSH1 1, SWAP1, SUB, DUP1, PUSH1 4, JUMPI, STOP:
+		// counts down from 50000 at 26 gas per iteration.
+		let code = vec![
+			0x62, 0x00, 0xc3, 0x50, 0x5b, 0x60, 0x01, 0x90, 0x03, 0x80, 0x60, 0x04, 0x57, 0x00,
+		];
+
+		with_executor(&Config::istanbul(), code.clone(), |executor| {
+			assert_eq!(call(executor, Vec::new()).0, ExitError::OutOfGas.into());
+		});
+
+		let mut config = Config::istanbul();
+		config.disable_gas_metering = true;
+		with_executor(&config, code, |executor| {
+			assert_eq!(call(executor, Vec::new()).0, ExitSucceed::Stopped.into());
+			assert_eq!(executor.used_gas(), 21_000);
+			assert_eq!(
+				executor.state().metadata().gasometer().nominal_gas(),
+				3 + 50_000 * 26
+			);
+		});
+	}
+
+	#[test]
+	fn disable_gas_metering_counts_memory() {
+		// PUSH1 1, PUSH2 0x0400, MSTORE, STOP: expands memory to 33 words.
+		let code = vec![0x60, 0x01, 0x61, 0x04, 0x00, 0x52, 0x00];
+		let execution = 3 + 3 + 3 + 33 * 3 + 33 * 33 / 512;
+
+		with_executor(&Config::istanbul(), code.clone(), |executor| {
+			assert_eq!(call(executor, Vec::new()).0, ExitSucceed::Stopped.into());
+			assert_eq!(executor.used_gas(), 21_000 + execution);
+		});
+
+		let mut config = Config::istanbul();
+		config.disable_gas_metering = true;
+		with_executor(&config, code, |executor| {
+			assert_eq!(call(executor, Vec::new()).0, ExitSucceed::Stopped.into());
+			assert_eq!(
+				executor.state().metadata().gasometer().nominal_gas(),
+				execution
+			);
+		});
+	}
+
+	#[test]
+	fn extcodecopy_of_empty_account_zero_fills() {
+		const EMPTY: H160 = H160::repeat_byte(0x09);
+		// PUSH32 0xff..ff, PUSH1 0, MSTORE, EXTCODECOPY(EMPTY, 0, 0, 64),
+		// PUSH1 64, PUSH1 0, RETURN
+		let mut code = vec![0x7f];
+		code.extend_from_slice(&[0xff; 32]);
+		code.extend_from_slice(&[0x60, 0x00, 0x52, 0x60, 0x40, 0x60, 0x00, 0x60, 0x00, 0x73]);
+		code.extend_from_slice(EMPTY.as_bytes());
+		code.extend_from_slice(&[0x3c, 0x60, 0x40, 0x60, 0x00, 0xf3]);
+
+		with_executor(&Config::istanbul(), code, |executor| {
+			assert_eq!(
+				call(executor, Vec::new()),
+				(ExitSucceed::Returned.into(), vec![0; 64])
+			);
+			// Eight pushes and MSTORE, EXTCODECOPY with two copied words, and
+			// two words of memory.
+			assert_eq!(executor.used_gas(), 21_000 + 9 * 3 + 700 + 2 * 3 + 2 * 3);
+		});
+	}
+
+	#[test]
+	fn invalid_opcode_consumes_all_gas() {
+		with_executor(&Config::istanbul(), vec![0xfe], |executor| {
+			assert_eq!(
+				call(executor, Vec::new()).0,
+				ExitError::DesignatedInvalid.into()
+			);
+			assert_eq!(executor.used_gas(), GAS_LIMIT);
+		});
+	}
+
+	#[test]
+	fn call_to_empty_code_transfers_value() {
+		const EOA: H160 = H160::repeat_byte(0x05);
+
+		with_executor(&Config::istanbul(), Vec::new(), |executor| {
+			assert_eq!(
+				executor.transact_call(
+					CALLER,
+					EOA,
+					U256::from(1000),
+					Vec::new(),
+					GAS_LIMIT,
+					Vec::new()
+				),
+				(ExitSucceed::Stopped.into(), Vec::new())
+			);
+			assert_eq!(executor.state().basic(EOA).balance, U256::from(1000));
+			assert_eq!(executor.used_gas(), 21_000);
+		});
+	}
+
+	#[test]
+	fn raised_create_contract_limit() {
+		// PUSH2 0x8000, PUSH1 0, RETURN: deploys 32KB of zeros.
+		let init_code = vec![0x61, 0x80, 0x00, 0x60, 0x00, 0xf3];
+		let gas_limit = 10_000_000;
+		let deploy = |config: &Config| {
+			with_executor_and_gas_limit(config, Vec::new(), gas_limit, |executor| {
+				let address =
+					executor.create_address(crate::CreateScheme::Legacy { caller: CALLER });
+				let reason = executor
+					.transact_create(
+						CALLER,
+						U256::zero(),
+						init_code.clone(),
+						gas_limit,
+						Vec::new(),
+					)
+					.0;
+				(reason, executor.state().code(address).len())
+			})
+		};
+
+		let mut config = Config::istanbul();
+		config.create_contract_limit = Some(0x10000);
+		assert_eq!(deploy(&config), (ExitSucceed::Returned.into(), 0x8000));
+		assert_eq!(
+			deploy(&Config::istanbul()),
+			(ExitError::CreateContractLimit.into(), 0)
+		);
+	}
+
+	#[test]
+	fn depth_of_nested_calls() {
+		const PROBE: H160 = H160::repeat_byte(0x0b);
+
+		/// Records the depth it is called at.
+		#[derive(Default)]
+		struct Probe(RefCell<Vec<usize>>);
+
+		impl PrecompileSet for Probe {
+			fn execute(&self, handle: &mut impl PrecompileHandle) -> Option<PrecompileResult> {
+				if handle.code_address() != PROBE {
+					return None;
+				}
+
+				self.0.borrow_mut().push(handle.depth());
+				Some(Ok(PrecompileOutput {
+					exit_status: ExitSucceed::Stopped,
+					output: Vec::new(),
+				}))
+			}
+
+			fn is_precompile(&self, address: H160) -> bool {
+				address == PROBE
+			}
+		}
+
+		// CALL(GAS, PROBE, 0, 0, 0, 0, 0), POP, STOP
+		let mut code = [0x60, 0x00].repeat(5);
+		code.push(0x73);
+		code.extend_from_slice(PROBE.as_bytes());
+		code.extend_from_slice(&[0x5a, 0xf1, 0x50, 0x00]);
+
+		let config = Config::istanbul();
+		let vicinity = vicinity();
+		let mut state = BTreeMap::new();
+		state.insert(
+			CONTRACT,
+			MemoryAccount {
+				code,
+				..Default::default()
+			},
+		);
+		let backend = MemoryBackend::new(&vicinity, state);
+		let metadata = StackSubstateMetadata::new(GAS_LIMIT, &config);
+		let state = MemoryStackState::new(metadata, &backend);
+		let probe = Probe::default();
+		let mut executor = StackExecutor::new_with_precompiles(state, &config, &probe);
+
+		assert_eq!(executor.depth(), 0);
+		for address in [PROBE, CONTRACT].iter() {
+			let (reason, _) = executor.transact_call(
+				CALLER,
+				*address,
+				U256::zero(),
+				Vec::new(),
+				100_000,
+				Vec::new(),
+			);
+			assert_eq!(reason, ExitSucceed::Stopped.into());
+		}
+
+		assert_eq!(*probe.0.borrow(), vec![0, 1]);
+		assert_eq!(executor.depth(), 0);
+	}
+
+	#[cfg(feature = "tracing")]
+	#[test]
+	fn call_tracer_builds_call_tree() {
+		use crate::tracing::{using, CallTraceKind, CallTracer};
+
+		fn call_code(address: H160) -> Vec<u8> {
+			// PUSH1 0 (x5), PUSH20 address, GAS, CALL, POP
+			let mut code = [0x60, 0x00].repeat(5);
+			code.push(0x73);
+			code.extend_from_slice(address.as_bytes());
+			code.extend_from_slice(&[0x5a, 0xf1, 0x50]);
+			code
+		}
+
+		let b = H160::repeat_byte(0xbb);
+		let c = H160::repeat_byte(0xcc);
+
+		// CONTRACT calls `b`, which calls `c`, then calls `c` itself.
+		let mut code = call_code(b);
+		code.extend(call_code(c));
+		with_executor(&Config::istanbul(), code, |executor| {
+			executor.state_mut().set_code(b, call_code(c));
+
+			let mut tracer = CallTracer::new();
+			let (reason, _) = using(&mut tracer, || call(executor, Vec::new()));
+			assert_eq!(reason, ExitSucceed::Stopped.into());
+
+			let trace = tracer.take_call_trace().unwrap();
+			assert_eq!(trace.kind, CallTraceKind::Call);
+			assert_eq!((trace.from, trace.to), (CALLER, CONTRACT));
+			assert_eq!(trace.reason, Some(ExitSucceed::Stopped.into()));
+			let edges = |trace: &crate::tracing::CallTrace| {
+				trace
+					.children
+					.iter()
+					.map(|child| (child.from, child.to, child.children.len()))
+					.collect::<Vec<_>>()
+			};
+			assert_eq!(edges(&trace), vec![(CONTRACT, b, 1), (CONTRACT, c, 0)]);
+			assert_eq!(edges(&trace.children[0]), vec![(b, c, 0)]);
+			assert!(tracer.take_call_trace().is_none());
+		});
+	}
+
+	#[test]
+	fn refund_quotient_by_fork() {
+		// PUSH1 1, PUSH1 0, SSTORE, PUSH1 0, PUSH1 0, SSTORE, STOP
+		let code = vec![
+			0x60, 0x01, 0x60, 0x00, 0x55, 0x60, 0x00, 0x60, 0x00, 0x55, 0x00,
+		];
+		let refund = |config: &Config| {
+			with_executor(config, code.clone(), |executor| {
+				assert_eq!(call(executor, Vec::new()).0, ExitSucceed::Stopped.into());
+				let gasometer = executor.state().metadata().gasometer();
+				(
+					gasometer.total_used_gas(),
+					gasometer.total_used_gas() - executor.used_gas(),
+				)
+			})
+		};
+
+		assert_eq!(Config::berlin().max_refund_quotient, 2);
+		assert_eq!(Config::london().max_refund_quotient, 5);
+
+		let (total, berlin_refund) = refund(&Config::berlin());
+		assert_eq!(berlin_refund, 19_900);
+		let (_, london_refund) = refund(&Config::london());
+		assert_eq!(london_refund, total / 5);
+		assert!(berlin_refund > london_refund);
+	}
+
+	#[test]
+	fn reentrancy_tracking() {
+		const PROBE: H160 = H160::repeat_byte(0x0b);
+
+		/// Records whether `CONTRACT` and `CALLER` are re-entrant, then calls
+		/// back into `CONTRACT` with one byte of call data.
+		#[derive(Default)]
+		struct Probe(RefCell<Vec<(bool, bool)>>);
+
+		impl PrecompileSet for Probe {
+			fn execute(&self, handle: &mut impl PrecompileHandle) -> Option<PrecompileResult> {
+				if handle.code_address() != PROBE {
+					return None;
+				}
+
+				self.0
+					.borrow_mut()
+					.push((handle.is_reentrant(CONTRACT), handle.is_reentrant(CALLER)));
+				let context = Context {
+					address: CONTRACT,
+					caller: PROBE,
+					apparent_value: U256::zero(),
+				};
+				let (reason, _) =
+					handle.call(CONTRACT, None, vec![0x01], Some(100_000), false, &context);
+				assert_eq!(reason, ExitSucceed::Stopped.into());
+
+				Some(Ok(PrecompileOutput {
+					exit_status: ExitSucceed::Stopped,
+					output: Vec::new(),
+				}))
+			}
+
+			fn is_precompile(&self, address: H160) -> bool {
+				address == PROBE
+			}
+		}
+
+		// CALLDATASIZE, PUSH1 38, JUMPI, CALL(GAS, PROBE, 0, 0, 0, 0, 0), POP,
+		// JUMPDEST, STOP: calls back into itself through the probe, unless
+		// given call data.
+		let mut code = vec![0x36, 0x60, 0x26, 0x57];
+		code.extend_from_slice(&[0x60, 0x00].repeat(5));
+		code.push(0x73);
+		code.extend_from_slice(PROBE.as_bytes());
+		code.extend_from_slice(&[0x5a, 0xf1, 0x50, 0x5b, 0x00]);
+
+		let config = Config::istanbul();
+		let vicinity = vicinity();
+		let mut state = BTreeMap::new();
+		state.insert(
+			CONTRACT,
+			MemoryAccount {
+				code,
+				..Default::default()
+			},
+		);
+		let backend = MemoryBackend::new(&vicinity, state);
+		let metadata = StackSubstateMetadata::new(GAS_LIMIT, &config);
+		let state = MemoryStackState::new(metadata, &backend);
+		let probe = Probe::default();
+		let mut executor = StackExecutor::new_with_precompiles(state, &config, &probe);
+
+		let mut transact = |address| {
+			let (reason, _) = executor.transact_call(
+				CALLER,
+				address,
+				U256::zero(),
+				Vec::new(),
+				100_000,
+				Vec::new(),
+			);
+			assert_eq!(reason, ExitSucceed::Stopped.into());
+		};
+		transact(PROBE);
+		transact(CONTRACT);
+
+		assert_eq!(*probe.0.borrow(), vec![(false, false), (true, false)]);
+		assert!(executor.call_stack_addresses().is_empty());
+	}
+
+	#[test]
+	fn call_into_self_destructed_account() {
+		let callee = H160::from_low_u64_be(0x10);
+		// CALLDATASIZE, PUSH1 14, JUMPI, MSTORE(0, 42), RETURN(0, 32),
+		// JUMPDEST, SELFDESTRUCT(0): self-destructs when given call data.
+		let callee_code = vec![
+			0x36, 0x60, 0x0e, 0x57, 0x60, 0x2a, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3,
+			0x5b, 0x60, 0x00, 0xff,
+		];
+		// CALL(GAS, 0x10, 0, 0, 1, 0, 0), POP, CALL(GAS, 0x10, 0, 0, 0, 0, 0),
+		// MSTORE(0, success), MSTORE(32, RETURNDATASIZE), RETURN(0, 64).
+		let code = vec![
+			0x60, 0x00, 0x60, 0x00, 0x60, 0x01, 0x60, 0x00, 0x60, 0x00, 0x60, 0x10, 0x5a, 0xf1,
+			0x50, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x00, 0x60, 0x10, 0x5a,
+			0xf1, 0x60, 0x00, 0x52, 0x3d, 0x60, 0x20, 0x52, 0x60, 0x40, 0x60, 0x00, 0xf3,
+		];
+
+		let run = |config: &Config| {
+			with_executor(config, code.clone(), |executor| {
+				executor.state_mut().set_code(callee, callee_code.clone());
+
+				let (reason, output) = call(executor, Vec::new());
+				assert_eq!(reason, ExitSucceed::Returned.into());
+				assert!(executor.state().deleted(callee));
+				(
+					output,
+					executor.code(callee),
+					executor.code_size(callee),
+					executor.code_hash(callee),
+				)
+			})
+		};
+
+		// The code stays until the end of the transaction and runs again.
+		let (output, code, size, hash) = run(&Config::istanbul());
+		assert_eq!(output, [word(1), word(32)].concat());
+		assert_eq!(code, callee_code);
+		assert_eq!(size, U256::from(callee_code.len()));
+		assert_eq!(hash, H256::from_slice(&Keccak256::digest(&callee_code)));
+
+		let mut config = Config::istanbul();
+		config.hide_deleted_code = true;
+		let (output, code, size, hash) = run(&config);
+		assert_eq!(output, [word(1), word(0)].concat());
+		assert!(code.is_empty());
+		assert_eq!(size, U256::zero());
+		assert_eq!(hash, H256::from_slice(&Keccak256::digest(b"")));
+	}
+
+	#[test]
+	fn legacy_create_address() {
+		let address = |hex: &str| H160::from_slice(&hex::decode(hex).unwrap());
+		let sender = address("6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0");
+		let nonce_0 = address("cd234a471b72ba2f1ccf0a70fcaba648a5eecd8d");
+		let nonce_1 = address("343c43a37d37dff08ae8c4a11544c718abb4fcf8");
+
+		with_executor(&Config::istanbul(), Vec::new(), |executor| {
+			let scheme = CreateScheme::Legacy { caller: sender };
+			assert_eq!(executor.create_address(scheme), nonce_0);
+			executor.state_mut().try_inc_nonce(sender).unwrap();
+			assert_eq!(executor.create_address(scheme), nonce_1);
+		});
+	}
+
+	#[test]
+	fn upfront_cost() {
+		assert_eq!(
+			Executor::upfront_cost(21_000, U256::from(2), U256::from(5)),
+			U256::from(42_005)
+		);
+		assert_eq!(
+			Executor::upfront_cost(u64::MAX, U256::MAX, U256::one()),
+			U256::MAX
+		);
+	}
+
+	#[test]
+	fn underfunded_transactions_are_rejected() {
+		// The caller holds 10_000_000 wei and gas is free.
+		let balance = U256::from(10_000_000);
+
+		// Off by default: the transaction runs and fails on the transfer.
+		with_executor(&Config::istanbul(), vec![0x00], |executor| {
+			let (reason, _) = executor.transact_call(
+				CALLER,
+				CONTRACT,
+				balance + 1,
+				Vec::new(),
+				GAS_LIMIT,
+				Vec::new(),
+			);
+			assert_eq!(reason, ExitError::OutOfFund.into());
+			assert_eq!(executor.nonce(CALLER), U256::one());
+			assert_eq!(executor.used_gas(), 21_000);
+		});
+
+		let mut config = Config::istanbul();
+		config.check_upfront_funds = true;
+		with_executor(&config, vec![0x00], |executor| {
+			let (reason, _) = executor.transact_call(
+				CALLER,
+				CONTRACT,
+				balance + 1,
+				Vec::new(),
+				GAS_LIMIT,
+				Vec::new(),
+			);
+			assert_eq!(reason, ExitError::OutOfFund.into());
+			let (reason, _) =
+				executor.transact_create(CALLER, balance + 1, Vec::new(), GAS_LIMIT, Vec::new());
+			assert_eq!(reason, ExitError::OutOfFund.into());
+			assert_eq!(executor.nonce(CALLER), U256::zero());
+			assert_eq!(executor.used_gas(), 0);
+
+			let (reason, _) = executor.transact_call(
+				CALLER,
+				CONTRACT,
+				balance,
+				Vec::new(),
+				GAS_LIMIT,
+				Vec::new(),
+			);
+			assert_eq!(reason, ExitSucceed::Stopped.into());
+			assert_eq!(executor.balance(CONTRACT), balance);
+		});
+	}
+
+	#[test]
+	fn addresses_are_left_padded_words() {
+		// ADDRESS, PUSH1 0, MSTORE, CALLER, PUSH1 32, MSTORE, RETURN(0, 64)
+		let code = vec![
+			0x30, 0x60, 0x00, 0x52, 0x33, 0x60, 0x20, 0x52, 0x60, 0x40, 0x60, 0x00, 0xf3,
+		];
+
+		let output = with_executor(&Config::istanbul(), code, |executor| {
+			call(executor, Vec::new()).1
+		});
+		assert_eq!(output[..12], [0u8; 12]);
+		assert_eq!(output[12..32], CONTRACT[..]);
+		assert_eq!(output[32..44], [0u8; 12]);
+		assert_eq!(output[44..], CALLER[..]);
+	}
+
+	#[test]
+	fn storage_snapshot_diff() {
+		// SSTORE(1, 7), SSTORE(2, 2), STOP
+		let code = vec![
+			0x60, 0x07, 0x60, 0x01, 0x55, 0x60, 0x02, 0x60, 0x02, 0x55, 0x00,
+		];
+		let slot = |n: u64| H256::from_low_u64_be(n);
+
+		with_executor(&Config::istanbul(), code, |executor| {
+			executor.state_mut().set_storage(CONTRACT, slot(1), slot(5));
+			executor.state_mut().set_storage(CONTRACT, slot(3), slot(9));
+			let before = executor.storage_snapshot(CONTRACT);
+			assert_eq!(before.len(), 2);
+
+			assert_eq!(call(executor, Vec::new()).0, ExitSucceed::Stopped.into());
+			let after = executor.storage_snapshot(CONTRACT);
+
+			assert_eq!(
+				storage_diff(&before, &after),
+				vec![
+					(slot(1), slot(5), slot(7)),
+					(slot(2), H256::zero(), slot(2))
+				]
+			);
+		});
+	}
+
+	#[test]
+	fn balance_cold_then_warm() {
+		let target = H160::repeat_byte(0x05);
+		let mut push_target = vec![0x73];
+		push_target.extend_from_slice(target.as_bytes());
+		// BALANCE(target), PUSH1 0, MSTORE, BALANCE(target), PUSH1 32, MSTORE,
+		// RETURN(0, 64)
+		let code = [
+			&push_target[..],
+			&[0x31, 0x60, 0x00, 0x52][..],
+			&push_target[..],
+			&[0x31, 0x60, 0x20, 0x52, 0x60, 0x40, 0x60, 0x00, 0xf3][..],
+		]
+		.concat();
+
+		with_executor(&Config::berlin(), code, |executor| {
+			executor.state_mut().deposit(target, U256::from(1234));
+			let (reason, output) = call(executor, Vec::new());
+			assert_eq!(reason, ExitSucceed::Returned.into());
+			assert_eq!(output, [word(1234), word(1234)].concat());
+
+			// The first BALANCE pays the cold surcharge, the second is warm.
+			let cold = 3 + 2600 + 3 + 6;
+			let warm = 3 + 100 + 3 + 6;
+			assert_eq!(executor.used_gas(), 21_000 + cold + warm + 3 + 3);
+		});
+	}
+
+	#[test]
+	fn forwarded_gas() {
+		with_executor(&Config::istanbul(), Vec::new(), |executor| {
+			let l64 = GAS_LIMIT - GAS_LIMIT / 64;
+			assert_eq!(executor.forwarded_gas(Some(u64::MAX)), l64);
+			assert_eq!(executor.forwarded_gas(None), l64);
+			assert_eq!(executor.forwarded_gas(Some(100)), 100);
+		});
+
+		with_executor(&Config::frontier(), Vec::new(), |executor| {
+			assert_eq!(executor.forwarded_gas(Some(u64::MAX)), GAS_LIMIT);
+		});
+	}
+
+	#[test]
+	fn access_list_forms_warm_alike() {
+		let slot = H256::from_low_u64_be(1);
+		// PUSH1 1, SLOAD, STOP
+		let code = vec![0x60, 0x01, 0x54, 0x00];
+		let pairs = vec![(CONTRACT, vec![slot])];
+		let typed = AccessList(vec![AccessListItem {
+			address: CONTRACT,
+			storage_keys: vec![slot],
+		}]);
+		assert_eq!(AccessList::from(pairs.clone()), typed);
+
+		// Passed to `transact_call` as is, in either form.
+		fn used_gas(code: Vec<u8>, access_list: impl Into<AccessList>) -> u64 {
+			with_executor(&Config::berlin(), code, |executor| {
+				let (reason, _) = executor.transact_call(
+					CALLER,
+					CONTRACT,
+					U256::zero(),
+					Vec::new(),
+					GAS_LIMIT,
+					access_list,
+				);
+				assert_eq!(reason, ExitSucceed::Stopped.into());
+				executor.used_gas()
+			})
+		}
+
+		// Access list costs 2400 + 1900, then a warm SLOAD.
+		let warm = 21_000 + 2400 + 1900 + 3 + 100;
+		assert_eq!(used_gas(code.clone(), pairs), warm);
+		assert_eq!(used_gas(code, typed), warm);
+	}
+
+	#[test]
+	fn simulate_create() {
+		// MSTORE8(0, 0x2a), RETURN(0, 1): deploys a single 0x2a byte.
+		let init_code = vec![0x60, 0x2a, 0x60, 0x00, 0x53, 0x60, 0x01, 0x60, 0x00, 0xf3];
+
+		with_executor(&Config::istanbul(), Vec::new(), |executor| {
+			let address = executor.create_address(CreateScheme::Legacy { caller: CALLER });
+
+			let (reason, code) =
+				executor.simulate_create(CALLER, U256::zero(), init_code.clone(), 100_000);
+			assert_eq!(reason, ExitSucceed::Returned.into());
+			assert_eq!(code, vec![0x2a]);
+			assert_eq!(executor.nonce(CALLER), U256::zero());
+			assert!(executor.code(address).is_empty());
+
+			let (reason, _) =
+				executor.transact_create(CALLER, U256::zero(), init_code, 100_000, Vec::new());
+			assert_eq!(reason, ExitSucceed::Returned.into());
+			assert_eq!(executor.code(address), code);
+		});
+	}
+
+	#[test]
+	fn call_stack_underflow_pops_nothing() {
+		// A bare CALL, and a CALL with only three of its seven arguments.
+		for (code, depth) in [
+			(vec![0xf1], 0),
+			(vec![0x60, 0x01, 0x60, 0x02, 0x60, 0x03, 0xf1], 3),
+		]
+		.iter()
+		{
+			with_executor(&Config::istanbul(), Vec::new(), |executor| {
+				let context = Context {
+					address: CONTRACT,
+					caller: CALLER,
+					apparent_value: U256::zero(),
+				};
+				let mut runtime = Runtime::new(
+					Rc::new(code.clone()),
+					Rc::new(Vec::new()),
+					context,
+					executor.config,
+				);
+
+				assert_eq!(
+					executor.execute(&mut runtime),
+					ExitError::StackUnderflow.into()
+				);
+				assert_eq!(runtime.machine().stack().len(), *depth);
+			});
+		}
+	}
+
+	#[test]
+	fn precompile_is_warm_in_create() {
+		let create_gas = |precompiles: &BTreeMap<H160, PrecompileFn>| {
+			with_executor_and_precompiles(
+				&Config::berlin(),
+				Vec::new(),
+				GAS_LIMIT,
+				precompiles,
+				|executor| {
+					// PUSH1 0x01, BALANCE, STOP
+					let (reason, _) = executor.transact_create(
+						CALLER,
+						U256::zero(),
+						vec![0x60, 0x01, 0x31, 0x00],
+						GAS_LIMIT,
+						Vec::new(),
+					);
+					assert_eq!(reason, ExitSucceed::Stopped.into());
+					executor.used_gas()
+				},
+			)
+		};
+
+		let mut precompiles = BTreeMap::new();
+		precompiles.insert(H160::from_low_u64_be(0x01), noop_precompile as PrecompileFn);
+
+		assert_eq!(
+			create_gas(&BTreeMap::new()) - create_gas(&precompiles),
+			2600 - 100
+		);
+	}
+
+	#[test]
+	fn logs_keep_emission_order_across_calls() {
+		// Without call data: LOG0 [1], CALL(GAS, CONTRACT, 0, 0, 1, 0, 0) with
+		// call data [1], LOG0 [3]. With call data: LOG0 [2].
+		let log = |byte| [0x60, byte, 0x60, 0x00, 0x53, 0x60, 0x01, 0x60, 0x00, 0xa0];
+		let mut code = vec![0x36, 0x60, 0x3b, 0x57];
+		code.extend_from_slice(&log(0x01));
+		code.extend_from_slice(&[
+			0x60, 0x00, 0x60, 0x00, 0x60, 0x01, 0x60, 0x00, 0x60, 0x00, 0x73,
+		]);
+		code.extend_from_slice(CONTRACT.as_bytes());
+		code.extend_from_slice(&[0x5a, 0xf1, 0x50]);
+		code.extend_from_slice(&log(0x03));
+		code.extend_from_slice(&[0x00, 0x5b]);
+		code.extend_from_slice(&log(0x02));
+		code.push(0x00);
+
+		with_executor(&Config::istanbul(), code, |executor| {
+			assert_eq!(call(executor, Vec::new()).0, ExitSucceed::Stopped.into());
+			let data = executor
+				.state()
+				.logs()
+				.into_iter()
+				.map(|log| log.data)
+				.collect::<Vec<_>>();
+			assert_eq!(data, vec![vec![1], vec![2], vec![3]]);
+		});
+	}
+
+	#[test]
+	fn log_limit() {
+		let mut config = Config::istanbul();
+		config.max_logs = Some(2);
+		// PUSH1 0, PUSH1 0, LOG0
+		let log0 = [0x60, 0x00, 0x60, 0x00, 0xa0];
+
+		with_executor(&config, Vec::new(), |executor| {
+			for _ in 0..2 {
+				assert_eq!(
+					Handler::log(executor, CONTRACT, Vec::new(), Vec::new()),
+					Ok(())
+				);
+			}
+			assert_eq!(
+				Handler::log(executor, CONTRACT, Vec::new(), vec![0x01]),
+				Err(ExitError::Other("log limit".into()))
+			);
+			assert_eq!(executor.state().logs().len(), 2);
+		});
+
+		with_executor(&config, log0.repeat(2), |executor| {
+			assert_eq!(call(executor, Vec::new()).0, ExitSucceed::Stopped.into());
+			// The limit applies to each transaction on its own.
+			assert_eq!(call(executor, Vec::new()).0, ExitSucceed::Stopped.into());
+			assert_eq!(executor.state().logs().len(), 4);
+		});
+		with_executor(&config, log0.repeat(3), |executor| {
+			assert_eq!(
+				call(executor, Vec::new()).0,
+				ExitError::Other("log limit".into()).into()
+			);
+		});
+	}
+
+	#[test]
+	fn deployed_code_after_create() {
+		// MSTORE(0, 0xdeadbeef), RETURN(28, 4)
+		let init_code = vec![
+			0x63, 0xde, 0xad, 0xbe, 0xef, 0x60, 0x00, 0x52, 0x60, 0x04, 0x60, 0x1c, 0xf3,
+		];
+
+		with_executor(&Config::istanbul(), Vec::new(), |executor| {
+			let address = executor.create_address(CreateScheme::Legacy { caller: CALLER });
+			assert!(executor.deployed_code(address).is_empty());
+
+			let (reason, _) =
+				executor.transact_create(CALLER, U256::zero(), init_code, GAS_LIMIT, Vec::new());
+			assert_eq!(reason, ExitSucceed::Returned.into());
+			assert_eq!(
+				executor.deployed_code(address),
+				vec![0xde, 0xad, 0xbe, 0xef]
+			);
+		});
+	}
+
+	#[test]
+	fn made_state_change() {
+		let run = |code: Vec<u8>| {
+			with_executor(&Config::istanbul(), Vec::new(), |executor| {
+				let context = Context {
+					address: CONTRACT,
+					caller: CALLER,
+					apparent_value: U256::zero(),
+				};
+				let mut runtime =
+					Runtime::new(Rc::new(code), Rc::new(Vec::new()), context, executor.config);
+				assert_eq!(executor.execute(&mut runtime), ExitSucceed::Stopped.into());
+				executor.state().made_state_change()
+			})
+		};
+
+		// SLOAD(0), BALANCE(CALLER), STOP
+		assert!(!run(vec![0x60, 0x00, 0x54, 0x33, 0x31, 0x00]));
+		// SSTORE(0, 1), STOP
+		assert!(run(vec![0x60, 0x01, 0x60, 0x00, 0x55, 0x00]));
+	}
+
+	#[test]
+	fn create2_includes_chain_id() {
+		let scheme = CreateScheme::Create2 {
+			caller: CALLER,
+			code_hash: H256::repeat_byte(0x0c),
+			salt: H256::repeat_byte(0x5a),
+		};
+		let create2_address = |config: &Config, chain_id: u64| {
+			let mut vicinity = vicinity();
+			vicinity.chain_id = U256::from(chain_id);
+			let backend = MemoryBackend::new(&vicinity, BTreeMap::new());
+			let metadata = StackSubstateMetadata::new(GAS_LIMIT, config);
+			let state = MemoryStackState::new(metadata, &backend);
+			let precompiles = BTreeMap::new();
+			let executor: Executor =
+				StackExecutor::new_with_precompiles(state, config, &precompiles);
+			executor.create_address(scheme)
+		};
+
+		let standard = Config::istanbul();
+		assert_eq!(create2_address(&standard, 1), create2_address(&standard, 2));
+
+		let mut separated = Config::istanbul();
+		separated.create2_includes_chain_id = true;
+		assert_ne!(
+			create2_address(&separated, 1),
+			create2_address(&separated, 2)
+		);
+		assert_ne!(
+			create2_address(&separated, 1),
+			create2_address(&standard, 1)
+		);
+	}
+
+	#[test]
+	fn block_override() {
+		// NUMBER, PUSH1 0, MSTORE, TIMESTAMP, PUSH1 32, MSTORE, PUSH1 64, PUSH1 0, RETURN
+		let code = vec![
+			0x43, 0x60, 0x00, 0x52, 0x42, 0x60, 0x20, 0x52, 0x60, 0x40, 0x60, 0x00, 0xf3,
+		];
+		let number_and_timestamp =
+			|number: usize, timestamp: usize| [word(number), word(timestamp)].concat();
+
+		with_executor(&Config::istanbul(), code, |executor| {
+			let original = executor.block_context();
+			let block = BlockContext {
+				number: U256::from(1_000),
+				timestamp: U256::from(1_700_000_000u64),
+				..original.clone()
+			};
+
+			let output = executor.with_block_override(block.clone(), |executor| {
+				assert_eq!(executor.block_context(), block);
+				call(executor, Vec::new()).1
+			});
+			assert_eq!(output, number_and_timestamp(1_000, 1_700_000_000));
+
+			assert_eq!(executor.block_context(), original);
+			assert_eq!(call(executor, Vec::new()).1, number_and_timestamp(1, 0));
+		});
+	}
+
+	#[test]
+	fn eip1559_fee() {
+		with_executor(&Config::london(), vec![0x00], |executor| {
+			assert_eq!(call(executor, Vec::new()).0, ExitSucceed::Stopped.into());
+			let used_gas = U256::from(executor.used_gas());
+			let block = BlockContext {
+				base_fee_per_gas: U256::from(100),
+				..executor.block_context()
+			};
+
+			executor.with_block_override(block, |executor| {
+				// Capped by the priority fee.
+				assert_eq!(
+					executor.eip1559_fee(U256::from(200), U256::from(10)),
+					used_gas * 110
+				);
+				// Capped by the max fee.
+				assert_eq!(
+					executor.eip1559_fee(U256::from(105), U256::from(10)),
+					used_gas * 105
+				);
+			});
+			assert_eq!(
+				executor.eip1559_fee(U256::from(105), U256::from(10)),
+				used_gas * 10
+			);
+		});
+	}
+
+	#[test]
+	fn block_override_block_hash_chain_id_and_blob_base_fee() {
+		// PUSH1 0, BLOCKHASH, PUSH1 0, MSTORE, CHAINID, PUSH1 32, MSTORE,
+		// BLOBBASEFEE, PUSH1 64, MSTORE, PUSH1 96, PUSH1 0, RETURN
+		let code = vec![
+			0x60, 0x00, 0x40, 0x60, 0x00, 0x52, 0x46, 0x60, 0x20, 0x52, 0x4a, 0x60, 0x40, 0x52,
+			0x60, 0x60, 0x60, 0x00, 0xf3,
+		];
+		let mut config = Config::istanbul();
+		config.has_blob_opcodes = true;
+		// The backend is at block 1 and knows the hash of block 0.
+		let hash = H256::repeat_byte(0xaa);
+		let vicinity = MemoryVicinity {
+			block_hashes: vec![hash],
+			..vicinity()
+		};
+		let mut state = BTreeMap::new();
+		state.insert(
+			CONTRACT,
+			MemoryAccount {
+				code,
+				..Default::default()
+			},
+		);
+		let backend = MemoryBackend::new(&vicinity, state);
+		let metadata = StackSubstateMetadata::new(GAS_LIMIT, &config);
+		let state = MemoryStackState::new(metadata, &backend);
+		let mut executor = StackExecutor::new_with_precompiles(state, &config, &());
+
+		let output = |block_hash: H256, chain_id: usize, blob_base_fee: usize| {
+			[block_hash.as_bytes(), &word(chain_id), &word(blob_base_fee)].concat()
+		};
+		fn run<'a>(
+			executor: &mut StackExecutor<'a, '_, MemoryStackState<'_, 'a, MemoryBackend>, ()>,
+		) -> Vec<u8> {
+			executor
+				.transact_call(
+					CALLER,
+					CONTRACT,
+					U256::zero(),
+					Vec::new(),
+					100_000,
+					Vec::new(),
+				)
+				.1
+		}
+
+		let original = executor.block_context();
+		let at = |number: u64| BlockContext {
+			number: U256::from(number),
+			chain_id: U256::from(5),
+			blob_base_fee: U256::from(9),
+			..original.clone()
+		};
+		assert_eq!(run(&mut executor), output(hash, 1, 7));
+		assert_eq!(executor.with_block_override(at(2), run), output(hash, 5, 9));
+		// Block 0 is the current block or more than 256 blocks back.
+		for number in [0, 300].iter() {
+			assert_eq!(
+				executor.with_block_override(at(*number), run),
+				output(H256::default(), 5, 9)
+			);
+		}
+		assert_eq!(run(&mut executor), output(hash, 1, 7));
+	}
+
+	#[test]
+	fn zero_length_return_and_revert_cost_no_memory() {
+		// PUSH1 0, PUSH2 0x1000, RETURN
+		with_executor(
+			&Config::istanbul(),
+			vec![0x60, 0x00, 0x61, 0x10, 0x00, 0xf3],
+			|executor| {
+				assert_eq!(
+					call(executor, Vec::new()),
+					(ExitSucceed::Returned.into(), Vec::new())
+				);
+				assert_eq!(executor.used_gas(), 21_000 + 3 + 3);
+			},
+		);
+		// PUSH1 0, PUSH2 0x1000, REVERT
+		with_executor(
+			&Config::istanbul(),
+			vec![0x60, 0x00, 0x61, 0x10, 0x00, 0xfd],
+			|executor| {
+				assert_eq!(
+					call(executor, Vec::new()),
+					(ExitRevert::Reverted.into(), Vec::new())
+				);
+				assert_eq!(executor.used_gas(), 21_000 + 3 + 3);
+			},
+		);
+	}
+
+	#[test]
+	fn nonce_is_capped() {
+		let max = U256::from(u64::MAX);
+
+		with_executor(&Config::berlin(), Vec::new(), |executor| {
+			executor.state_mut().account_mut(CALLER).basic.nonce = max - 1;
+			assert_eq!(executor.state_mut().try_inc_nonce(CALLER), Ok(()));
+			assert_eq!(executor.nonce(CALLER), max);
+			assert_eq!(
+				executor.state_mut().try_inc_nonce(CALLER),
+				Err(ExitError::MaxNonce)
+			);
+			assert_eq!(executor.nonce(CALLER), max);
+
+			let (reason, _) =
+				executor.transact_create(CALLER, U256::zero(), vec![0x00], GAS_LIMIT, Vec::new());
+			assert_eq!(reason, ExitError::MaxNonce.into());
+			assert_eq!(executor.nonce(CALLER), max);
+
+			// Rejected before the intrinsic cost and access list warming.
+			let (reason, _) = executor.transact_call(
+				CALLER,
+				CONTRACT,
+				U256::zero(),
+				Vec::new(),
+				GAS_LIMIT,
+				vec![(CONTRACT, vec![H256::zero()])],
+			);
+			assert_eq!(reason, ExitError::MaxNonce.into());
+			assert_eq!(executor.nonce(CALLER), max);
+			assert_eq!(executor.used_gas(), 0);
+			assert!(executor.is_cold(CONTRACT, None));
+			assert!(executor.is_cold(CONTRACT, Some(H256::zero())));
+		});
+	}
+
+	#[test]
+	fn run_code() {
+		let vicinity = vicinity();
+		let mut backend = MemoryBackend::new(&vicinity, BTreeMap::new());
+		// PUSH1 0x01, PUSH1 0x00, SSTORE, PUSH1 0x2a, PUSH1 0x00, MSTORE,
+		// PUSH1 0x20, PUSH1 0x00, RETURN
+		let code = vec![
+			0x60, 0x01, 0x60, 0x00, 0x55, 0x60, 0x2a, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00,
+			0xf3,
+		];
+
+		assert_eq!(
+			super::run_code(code, Vec::new(), &mut backend, &Config::istanbul()),
+			(ExitSucceed::Returned.into(), word(42))
+		);
+		assert_eq!(
+			backend.storage(super::RUN_CODE_ADDRESS, H256::zero()),
+			H256::from_low_u64_be(1)
+		);
+		assert!(backend.code(super::RUN_CODE_ADDRESS).is_empty());
+
+		// Metered: an infinite loop runs out of the block gas limit.
+		// JUMPDEST, PUSH1 0x00, JUMP
+		let code = vec![0x5b, 0x60, 0x00, 0x56];
+		let vicinity = MemoryVicinity {
+			block_gas_limit: U256::from(GAS_LIMIT),
+			..vicinity
+		};
+		let mut backend = MemoryBackend::new(&vicinity, BTreeMap::new());
+		assert_eq!(
+			super::run_code(code, Vec::new(), &mut backend, &Config::istanbul()).0,
+			ExitError::OutOfGas.into()
+		);
+	}
+
+	#[test]
+	fn accessed_storage_list() {
+		// PUSH1 2, SLOAD, PUSH1 1, SLOAD, STOP
+		let code = vec![0x60, 0x02, 0x54, 0x60, 0x01, 0x54, 0x00];
+
+		with_executor(&Config::berlin(), code.clone(), |executor| {
+			assert_eq!(call(executor, Vec::new()).0, ExitSucceed::Stopped.into());
+			assert_eq!(
+				executor.accessed_storage_list(),
+				vec![
+					(CONTRACT, H256::from_low_u64_be(1)),
+					(CONTRACT, H256::from_low_u64_be(2)),
+				]
+			);
+		});
+		with_executor(&Config::istanbul(), code, |executor| {
+			assert_eq!(call(executor, Vec::new()).0, ExitSucceed::Stopped.into());
+			assert!(executor.accessed_storage_list().is_empty());
+		});
+	}
+
+	#[test]
+	fn precompile_logs() {
+		const EMITTER: H160 = H160::repeat_byte(0x0a);
+
+		/// Logs its input through the handle.
+		struct Emitter;
+
+		fn emit(handle: &mut impl PrecompileHandle) -> PrecompileResult {
+			let data = handle.input().to_vec();
+			handle.log(handle.code_address(), vec![H256::repeat_byte(0x01)], data)?;
+			Ok(PrecompileOutput {
+				exit_status: ExitSucceed::Returned,
+				output: Vec::new(),
+			})
+		}
+
+		impl PrecompileSet for Emitter {
+			fn execute(&self, handle: &mut impl PrecompileHandle) -> Option<PrecompileResult> {
+				if handle.code_address() != EMITTER {
+					return None;
+				}
+
+				Some(emit(handle))
+			}
+
+			fn is_precompile(&self, address: H160) -> bool {
+				address == EMITTER
+			}
+		}
+
+		// STATICCALL(GAS, EMITTER, 0, 0, 0, 0), MSTORE(0, success), RETURN(0, 32)
+		let mut code = [0x60, 0x00].repeat(4);
+		code.push(0x73);
+		code.extend_from_slice(EMITTER.as_bytes());
+		code.extend_from_slice(&[0x5a, 0xfa, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3]);
+
+		with_executor_and_precompiles(&Config::istanbul(), code, GAS_LIMIT, &Emitter, |executor| {
+			let (reason, _) = executor.transact_call(
+				CALLER,
+				EMITTER,
+				U256::zero(),
+				vec![0x42],
+				100_000,
+				Vec::new(),
+			);
+			assert_eq!(reason, ExitSucceed::Returned.into());
+			let logs = [Log {
+				address: EMITTER,
+				topics: vec![H256::repeat_byte(0x01)],
+				data: vec![0x42],
+			}];
+			assert_eq!(executor.state().logs(), &logs[..]);
+
+			// Logging fails the static call, and nothing is logged.
+			assert_eq!(
+				executor.transact_call(
+					CALLER,
+					CONTRACT,
+					U256::zero(),
+					Vec::new(),
+					100_000,
+					Vec::new()
+				),
+				(ExitSucceed::Returned.into(), word(0))
+			);
+			assert_eq!(executor.state().logs(), &logs[..]);
+		});
+	}
+
+	#[test]
+	fn initcode_too_large() {
+		let mut config = Config::istanbul();
+		config.max_initcode_size = Some(64);
+		config.create_contract_limit = Some(32);
+
+		with_executor(&config, Vec::new(), |executor| {
+			let (reason, _) =
+				executor.transact_create(CALLER, U256::zero(), vec![0; 65], GAS_LIMIT, Vec::new());
+			assert_eq!(reason, ExitError::InitcodeTooLarge.into());
+
+			// RETURN(0, 33): small init code deploying oversized code.
+			let (reason, _) = executor.transact_create(
+				CALLER,
+				U256::zero(),
+				vec![0x60, 0x21, 0x60, 0x00, 0xf3],
+				GAS_LIMIT,
+				Vec::new(),
+			);
+			assert_eq!(reason, ExitError::CreateContractLimit.into());
+		});
+
+		// CREATE(0, 0, 65), STOP
+		let code = vec![0x60, 0x41, 0x60, 0x00, 0x60, 0x00, 0xf0, 0x00];
+		with_executor(&config, code, |executor| {
+			assert_eq!(
+				call(executor, Vec::new()).0,
+				ExitError::InitcodeTooLarge.into()
+			);
+		});
+	}
+
+	#[test]
+	fn top_level_context() {
+		let context = Context::top_level(CALLER, CONTRACT, U256::from(7));
+		assert_eq!(context.caller, CALLER);
+		assert_eq!(context.address, CONTRACT);
+		assert_eq!(context.apparent_value, U256::from(7));
+
+		// CALLER, ADDRESS, CALLVALUE, then return the three words.
+		let code = vec![
+			0x33, 0x60, 0x00, 0x52, 0x30, 0x60, 0x20, 0x52, 0x34, 0x60, 0x40, 0x52, 0x60, 0x60,
+			0x60, 0x00, 0xf3,
+		];
+		with_executor(&Config::istanbul(), code, |executor| {
+			let (reason, output) = executor.transact_call(
+				CALLER,
+				CONTRACT,
+				U256::from(7),
+				Vec::new(),
+				GAS_LIMIT,
+				Vec::new(),
+			);
+			assert_eq!(reason, ExitSucceed::Returned.into());
+			assert_eq!(
+				output,
+				[
+					H256::from(CALLER).as_bytes(),
+					H256::from(CONTRACT).as_bytes(),
+					&word(7)[..],
+				]
+				.concat()
+			);
+		});
+	}
+
+	#[test]
+	fn sub_intrinsic_gas_limit() {
+		// Call intrinsic is 21000, create intrinsic 53000 for empty init code.
+		for gas_limit in [0, 20_999].iter() {
+			with_executor(&Config::istanbul(), vec![0x00], |executor| {
+				let result = executor.transact_call(
+					CALLER,
+					CONTRACT,
+					U256::zero(),
+					Vec::new(),
+					*gas_limit,
+					Vec::new(),
+				);
+				assert_eq!(result, (ExitError::OutOfGas.into(), Vec::new()));
+				assert_eq!(executor.nonce(CALLER), U256::zero());
+				assert_eq!(executor.used_gas(), 0);
+				assert!(!executor.state().made_state_change());
+			});
+		}
+
+		for gas_limit in [0, 52_999].iter() {
+			with_executor(&Config::istanbul(), Vec::new(), |executor| {
+				let result = executor.transact_create(
+					CALLER,
+					U256::zero(),
+					Vec::new(),
+					*gas_limit,
+					Vec::new(),
+				);
+				assert_eq!(result, (ExitError::OutOfGas.into(), Vec::new()));
+				assert_eq!(executor.nonce(CALLER), U256::zero());
+				assert_eq!(executor.used_gas(), 0);
+				assert!(!executor.state().made_state_change());
+			});
+		}
+	}
 }
@@ -6,7 +6,7 @@ use alloc::{
 	collections::{BTreeMap, BTreeSet},
 	vec::Vec,
 };
-use core::mem;
+use core::{fmt, mem};
 use primitive_types::{H160, H256, U256};
 
 #[derive(Clone, Debug)]
@@ -24,6 +24,9 @@ pub struct MemoryStackSubstate<'config> {
 	accounts: BTreeMap<H160, MemoryStackAccount>,
 	storages: BTreeMap<(H160, H256), H256>,
 	deletes: BTreeSet<H160>,
+	modified: BTreeSet<H160>,
+	resets: BTreeSet<H160>,
+	state_changed: bool,
 }
 
 impl<'config> MemoryStackSubstate<'config> {
@@ -35,6 +38,9 @@ impl<'config> MemoryStackSubstate<'config> {
 			accounts: BTreeMap::new(),
 			storages: BTreeMap::new(),
 			deletes: BTreeSet::new(),
+			modified: BTreeSet::new(),
+			resets: BTreeSet::new(),
+			state_changed: false,
 		}
 	}
 
@@ -46,6 +52,29 @@ impl<'config> MemoryStackSubstate<'config> {
 		&mut self.logs
 	}
 
+	pub fn all_logs(&self) -> Vec<Log> {
+		let mut logs = self
+			.parent
+			.as_ref()
+			.map(|parent| parent.all_logs())
+			.unwrap_or_default();
+		logs.extend_from_slice(&self.logs);
+		logs
+	}
+
+	pub fn log_count(&self) -> usize {
+		let parent_count = self
+			.parent
+			.as_ref()
+			.map(|parent| parent.log_count())
+			.unwrap_or_default();
+		parent_count + self.logs.len()
+	}
+
+	pub fn made_state_change(&self) -> bool {
+		self.state_changed
+	}
+
 	pub fn metadata(&self) -> &StackSubstateMetadata<'config> {
 		&self.metadata
 	}
@@ -113,13 +142,24 @@ impl<'config> MemoryStackSubstate<'config> {
 	}
 
 	pub fn enter(&mut self, gas_limit: u64, is_static: bool) {
+		self.enter_with(self.metadata.spit_child(gas_limit, is_static));
+	}
+
+	pub fn enter_checkpoint(&mut self, gas_limit: u64) {
+		self.enter_with(self.metadata.spit_checkpoint(gas_limit));
+	}
+
+	fn enter_with(&mut self, metadata: StackSubstateMetadata<'config>) {
 		let mut entering = Self {
-			metadata: self.metadata.spit_child(gas_limit, is_static),
+			metadata,
 			parent: None,
 			logs: Vec::new(),
 			accounts: BTreeMap::new(),
 			storages: BTreeMap::new(),
 			deletes: BTreeSet::new(),
+			modified: BTreeSet::new(),
+			resets: BTreeSet::new(),
+			state_changed: false,
 		};
 		mem::swap(&mut entering, self);
 
@@ -152,6 +192,9 @@ impl<'config> MemoryStackSubstate<'config> {
 		self.accounts.append(&mut exited.accounts);
 		self.storages.append(&mut exited.storages);
 		self.deletes.append(&mut exited.deletes);
+		self.modified.append(&mut exited.modified);
+		self.resets.append(&mut exited.resets);
+		self.state_changed |= exited.state_changed;
 
 		Ok(())
 	}
@@ -232,6 +275,26 @@ impl<'config> MemoryStackSubstate<'config> {
 		None
 	}
 
+	/// Non-zero storage entries of address, as seen from this substate.
+	pub fn storage_entries<B: Backend>(&self, address: H160, backend: &B) -> BTreeMap<H256, H256> {
+		let mut entries = match self.parent.as_ref() {
+			Some(parent) => parent.storage_entries(address, backend),
+			None => backend.storage_entries(address),
+		};
+
+		if matches!(self.accounts.get(&address), Some(account) if account.reset) {
+			entries.clear();
+		}
+		for ((oa, ok), ov) in &self.storages {
+			if *oa == address {
+				entries.insert(*ok, *ov);
+			}
+		}
+		entries.retain(|_, value| *value != H256::default());
+
+		entries
+	}
+
 	pub fn known_original_storage(&self, address: H160, key: H256) -> Option<H256> {
 		if let Some(account) = self.accounts.get(&address) {
 			if account.reset {
@@ -266,6 +329,17 @@ impl<'config> MemoryStackSubstate<'config> {
 		}
 	}
 
+	pub fn touched_accounts(&self) -> BTreeSet<H160> {
+		let mut touched = self
+			.parent
+			.as_ref()
+			.map(|parent| parent.touched_accounts())
+			.unwrap_or_default();
+		touched.extend(self.modified.iter().copied());
+		touched.extend(self.deletes.iter().copied());
+		touched
+	}
+
 	pub fn deleted(&self, address: H160) -> bool {
 		if self.deletes.contains(&address) {
 			return true;
@@ -303,10 +377,30 @@ impl<'config> MemoryStackSubstate<'config> {
 
 	pub fn inc_nonce<B: Backend>(&mut self, address: H160, backend: &B) {
 		self.account_mut(address, backend).basic.nonce += U256::one();
+		self.modified.insert(address);
+		self.state_changed = true;
+	}
+
+	pub fn try_inc_nonce<B: Backend>(
+		&mut self,
+		address: H160,
+		backend: &B,
+	) -> Result<(), ExitError> {
+		let nonce = &mut self.account_mut(address, backend).basic.nonce;
+		if *nonce >= U256::from(u64::MAX) {
+			return Err(ExitError::MaxNonce);
+		}
+		*nonce += U256::one();
+		self.modified.insert(address);
+		self.state_changed = true;
+
+		Ok(())
 	}
 
 	pub fn set_storage(&mut self, address: H160, key: H256, value: H256) {
 		self.storages.insert((address, key), value);
+		self.modified.insert(address);
+		self.state_changed = true;
 	}
 
 	pub fn reset_storage<B: Backend>(&mut self, address: H160, backend: &B) {
@@ -323,6 +417,9 @@ impl<'config> MemoryStackSubstate<'config> {
 		}
 
 		self.account_mut(address, backend).reset = true;
+		self.modified.insert(address);
+		self.resets.insert(address);
+		self.state_changed = true;
 	}
 
 	pub fn log(&mut self, address: H160, topics: Vec<H256>, data: Vec<u8>) {
@@ -331,14 +428,18 @@ impl<'config> MemoryStackSubstate<'config> {
 			topics,
 			data,
 		});
+		self.state_changed = true;
 	}
 
 	pub fn set_deleted(&mut self, address: H160) {
 		self.deletes.insert(address);
+		self.state_changed = true;
 	}
 
 	pub fn set_code<B: Backend>(&mut self, address: H160, code: Vec<u8>, backend: &B) {
 		self.account_mut(address, backend).code = Some(code);
+		self.modified.insert(address);
+		self.state_changed = true;
 	}
 
 	pub fn transfer<B: Backend>(
@@ -351,6 +452,10 @@ impl<'config> MemoryStackSubstate<'config> {
 			if source.basic.balance < transfer.value {
 				return Err(ExitError::OutOfFund);
 			}
+			// A self-transfer leaves the balance as is once it is covered.
+			if transfer.source == transfer.target {
+				return Ok(());
+			}
 			source.basic.balance -= transfer.value;
 		}
 
@@ -359,6 +464,12 @@ impl<'config> MemoryStackSubstate<'config> {
 			target.basic.balance = target.basic.balance.saturating_add(transfer.value);
 		}
 
+		if !transfer.value.is_zero() {
+			self.modified.insert(transfer.source);
+			self.modified.insert(transfer.target);
+			self.state_changed = true;
+		}
+
 		Ok(())
 	}
 
@@ -374,6 +485,8 @@ impl<'config> MemoryStackSubstate<'config> {
 			return Err(ExitError::OutOfFund);
 		}
 		source.basic.balance -= value;
+		self.modified.insert(address);
+		self.state_changed = true;
 
 		Ok(())
 	}
@@ -382,10 +495,14 @@ impl<'config> MemoryStackSubstate<'config> {
 	pub fn deposit<B: Backend>(&mut self, address: H160, value: U256, backend: &B) {
 		let target = self.account_mut(address, backend);
 		target.basic.balance = target.basic.balance.saturating_add(value);
+		self.modified.insert(address);
+		self.state_changed = true;
 	}
 
 	pub fn reset_balance<B: Backend>(&mut self, address: H160, backend: &B) {
 		self.account_mut(address, backend).basic.balance = U256::zero();
+		self.modified.insert(address);
+		self.state_changed = true;
 	}
 
 	pub fn touch<B: Backend>(&mut self, address: H160, backend: &B) {
@@ -393,10 +510,35 @@ impl<'config> MemoryStackSubstate<'config> {
 	}
 }
 
-#[derive(Clone, Debug)]
+/// Callback receiving the address, key and value of a committed storage write.
+pub type StorageObserver<'a> = Box<dyn FnMut(H160, H256, H256) + 'a>;
+
 pub struct MemoryStackState<'backend, 'config, B> {
 	backend: &'backend B,
 	substate: MemoryStackSubstate<'config>,
+	storage_observer: Option<StorageObserver<'backend>>,
+}
+
+/// The storage observer is not cloned: writes of a cloned state are not
+/// reported.
+impl<'backend, 'config, B> Clone for MemoryStackState<'backend, 'config, B> {
+	fn clone(&self) -> Self {
+		Self {
+			backend: self.backend,
+			substate: self.substate.clone(),
+			storage_observer: None,
+		}
+	}
+}
+
+impl<'backend, 'config, B: fmt::Debug> fmt::Debug for MemoryStackState<'backend, 'config, B> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("MemoryStackState")
+			.field("backend", &self.backend)
+			.field("substate", &self.substate)
+			.field("storage_observer", &self.storage_observer.is_some())
+			.finish()
+	}
 }
 
 impl<'backend, 'config, B: Backend> Backend for MemoryStackState<'backend, 'config, B> {
@@ -421,12 +563,21 @@ impl<'backend, 'config, B: Backend> Backend for MemoryStackState<'backend, 'conf
 	fn block_difficulty(&self) -> U256 {
 		self.backend.block_difficulty()
 	}
+	fn block_randomness(&self) -> Option<H256> {
+		self.backend.block_randomness()
+	}
 	fn block_gas_limit(&self) -> U256 {
 		self.backend.block_gas_limit()
 	}
 	fn block_base_fee_per_gas(&self) -> U256 {
 		self.backend.block_base_fee_per_gas()
 	}
+	fn blob_base_fee(&self) -> U256 {
+		self.backend.blob_base_fee()
+	}
+	fn blob_hash(&self, index: U256) -> H256 {
+		self.backend.blob_hash(index)
+	}
 
 	fn chain_id(&self) -> U256 {
 		self.backend.chain_id()
@@ -461,6 +612,10 @@ impl<'backend, 'config, B: Backend> Backend for MemoryStackState<'backend, 'conf
 
 		self.backend.original_storage(address, key)
 	}
+
+	fn storage_entries(&self, address: H160) -> BTreeMap<H256, H256> {
+		self.substate.storage_entries(address, self.backend)
+	}
 }
 
 impl<'backend, 'config, B: Backend> StackState<'config> for MemoryStackState<'backend, 'config, B> {
@@ -476,8 +631,36 @@ impl<'backend, 'config, B: Backend> StackState<'config> for MemoryStackState<'ba
 		self.substate.enter(gas_limit, is_static)
 	}
 
+	fn enter_checkpoint(&mut self, gas_limit: u64) {
+		self.substate.enter_checkpoint(gas_limit)
+	}
+
 	fn exit_commit(&mut self) -> Result<(), ExitError> {
-		self.substate.exit_commit()
+		let mut cleared = Vec::new();
+		let mut committed = BTreeMap::new();
+		if let (Some(_), Some(root)) = (&self.storage_observer, &self.substate.parent) {
+			if root.parent.is_none() {
+				for address in &self.substate.resets {
+					for key in root.storage_entries(*address, self.backend).into_keys() {
+						cleared.push((*address, key));
+					}
+				}
+				committed = self.substate.storages.clone();
+			}
+		}
+
+		self.substate.exit_commit()?;
+
+		if let Some(observer) = self.storage_observer.as_mut() {
+			for (address, key) in cleared {
+				observer(address, key, H256::default());
+			}
+			for ((address, key), value) in committed {
+				observer(address, key, value);
+			}
+		}
+
+		Ok(())
 	}
 
 	fn exit_revert(&mut self) -> Result<(), ExitError> {
@@ -514,18 +697,56 @@ impl<'backend, 'config, B: Backend> StackState<'config> for MemoryStackState<'ba
 		self.substate.inc_nonce(address, self.backend);
 	}
 
+	fn try_inc_nonce(&mut self, address: H160) -> Result<(), ExitError> {
+		self.substate.try_inc_nonce(address, self.backend)
+	}
+
 	fn set_storage(&mut self, address: H160, key: H256, value: H256) {
-		self.substate.set_storage(address, key, value)
+		self.substate.set_storage(address, key, value);
+
+		if self.substate.parent.is_none() {
+			if let Some(observer) = self.storage_observer.as_mut() {
+				observer(address, key, value);
+			}
+		}
 	}
 
 	fn reset_storage(&mut self, address: H160) {
+		let cleared = if self.storage_observer.is_some() && self.substate.parent.is_none() {
+			self.substate.storage_entries(address, self.backend)
+		} else {
+			BTreeMap::new()
+		};
+
 		self.substate.reset_storage(address, self.backend);
+
+		if let Some(observer) = self.storage_observer.as_mut() {
+			for key in cleared.into_keys() {
+				observer(address, key, H256::default());
+			}
+		}
 	}
 
 	fn log(&mut self, address: H160, topics: Vec<H256>, data: Vec<u8>) {
 		self.substate.log(address, topics, data);
 	}
 
+	fn logs(&self) -> Vec<Log> {
+		self.substate.all_logs()
+	}
+
+	fn log_count(&self) -> usize {
+		self.substate.log_count()
+	}
+
+	fn touched_accounts(&self) -> BTreeSet<H160> {
+		self.substate.touched_accounts()
+	}
+
+	fn made_state_change(&self) -> bool {
+		self.substate.made_state_change()
+	}
+
 	fn set_deleted(&mut self, address: H160) {
 		self.substate.set_deleted(address)
 	}
@@ -552,9 +773,18 @@ impl<'backend, 'config, B: Backend> MemoryStackState<'backend, 'config, B> {
 		Self {
 			backend,
 			substate: MemoryStackSubstate::new(metadata),
+			storage_observer: None,
 		}
 	}
 
+	/// Report storage writes once they are committed to the top-level
+	/// substate. Writes of reverted or discarded substates are never reported.
+	/// Clearing the storage of an account is reported as a zero write to each
+	/// slot it held.
+	pub fn set_storage_observer(&mut self, observer: impl FnMut(H160, H256, H256) + 'backend) {
+		self.storage_observer = Some(Box::new(observer));
+	}
+
 	/// Returns a mutable reference to an account given its address
 	pub fn account_mut(&mut self, address: H160) -> &mut MemoryStackAccount {
 		self.substate.account_mut(address, self.backend)
@@ -578,3 +808,164 @@ impl<'backend, 'config, B: Backend> MemoryStackState<'backend, 'config, B> {
 		self.substate.deposit(address, value, self.backend)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::MemoryStackState;
+	use crate::backend::{Backend, MemoryAccount, MemoryBackend};
+	use crate::executor::stack::{StackExecutor, StackState, StackSubstateMetadata};
+	use crate::test_utils::{vicinity, CALLER, CONTRACT, GAS_LIMIT};
+	use crate::{Config, ExitError, ExitRevert, ExitSucceed, Transfer};
+	use alloc::collections::BTreeMap;
+	use core::cell::RefCell;
+	use primitive_types::{H160, H256, U256};
+	use sha3::{Digest, Keccak256};
+
+	#[test]
+	fn storage_observer_sees_committed_writes() {
+		// SSTORE(1, 1), SSTORE(3, 2), then REVERT(0, 0) with call data or STOP
+		// without.
+		let code = vec![
+			0x60, 0x01, 0x60, 0x01, 0x55, 0x60, 0x02, 0x60, 0x03, 0x55, 0x36, 0x60, 0x0f, 0x57,
+			0x00, 0x5b, 0x60, 0x00, 0x80, 0xfd,
+		];
+		let writes = RefCell::new(Vec::new());
+		let vicinity = vicinity();
+		let mut accounts = BTreeMap::new();
+		accounts.insert(
+			CONTRACT,
+			MemoryAccount {
+				code,
+				..Default::default()
+			},
+		);
+		let backend = MemoryBackend::new(&vicinity, accounts);
+		let config = Config::istanbul();
+		let metadata = StackSubstateMetadata::new(GAS_LIMIT, &config);
+		let mut state = MemoryStackState::new(metadata, &backend);
+		state.set_storage_observer(|address, key, value| {
+			writes.borrow_mut().push((address, key, value))
+		});
+		let mut executor = StackExecutor::new_with_precompiles(state, &config, &());
+
+		let (reason, _) = executor.transact_call(
+			CALLER,
+			CONTRACT,
+			U256::zero(),
+			vec![0x01],
+			100_000,
+			Vec::new(),
+		);
+		assert_eq!(reason, ExitRevert::Reverted.into());
+		assert!(writes.borrow().is_empty());
+
+		let (reason, _) = executor.transact_call(
+			CALLER,
+			CONTRACT,
+			U256::zero(),
+			Vec::new(),
+			100_000,
+			Vec::new(),
+		);
+		assert_eq!(reason, ExitSucceed::Stopped.into());
+		let word = H256::from_low_u64_be;
+		assert_eq!(
+			*writes.borrow(),
+			vec![(CONTRACT, word(1), word(1)), (CONTRACT, word(3), word(2))]
+		);
+	}
+
+	#[test]
+	fn storage_observer_sees_reset_storage() {
+		// Address created by CALLER at nonce 0, from keccak(rlp([CALLER, 0])).
+		let created = H160::from_slice(
+			&Keccak256::digest([&[0xd6, 0x94][..], CALLER.as_bytes(), &[0x80]].concat())[12..],
+		);
+		let slot = H256::repeat_byte(0x01);
+		let writes = RefCell::new(Vec::new());
+		let vicinity = vicinity();
+		let mut storage = BTreeMap::new();
+		storage.insert(slot, H256::repeat_byte(0x02));
+		let mut accounts = BTreeMap::new();
+		accounts.insert(
+			created,
+			MemoryAccount {
+				storage,
+				..Default::default()
+			},
+		);
+		let backend = MemoryBackend::new(&vicinity, accounts);
+		let config = Config::istanbul();
+		let metadata = StackSubstateMetadata::new(GAS_LIMIT, &config);
+		let mut state = MemoryStackState::new(metadata, &backend);
+		state.set_storage_observer(|address, key, value| {
+			writes.borrow_mut().push((address, key, value))
+		});
+		let mut executor = StackExecutor::new_with_precompiles(state, &config, &());
+
+		let (reason, _) =
+			executor.transact_create(CALLER, U256::zero(), Vec::new(), 100_000, Vec::new());
+		assert!(reason.is_succeed());
+		assert_eq!(*writes.borrow(), vec![(created, slot, H256::default())]);
+	}
+
+	#[test]
+	fn self_transfer() {
+		let account = H160::repeat_byte(0x01);
+		let vicinity = vicinity();
+		let mut accounts = BTreeMap::new();
+		accounts.insert(
+			account,
+			MemoryAccount {
+				balance: U256::from(100),
+				..Default::default()
+			},
+		);
+		let backend = MemoryBackend::new(&vicinity, accounts);
+		let config = Config::istanbul();
+		let metadata = StackSubstateMetadata::new(1_000_000, &config);
+		let mut state = MemoryStackState::new(metadata, &backend);
+		let transfer = |value: u64| Transfer {
+			source: account,
+			target: account,
+			value: U256::from(value),
+		};
+
+		assert_eq!(state.transfer(transfer(60)), Ok(()));
+		assert_eq!(state.basic(account).balance, U256::from(100));
+		assert_eq!(state.transfer(transfer(101)), Err(ExitError::OutOfFund));
+		assert_eq!(state.basic(account).balance, U256::from(100));
+	}
+
+	#[test]
+	fn is_creatable() {
+		let with_code = H160::repeat_byte(0x01);
+		let with_nonce = H160::repeat_byte(0x02);
+		let fresh = H160::repeat_byte(0x03);
+
+		let vicinity = vicinity();
+		let mut accounts = BTreeMap::new();
+		accounts.insert(
+			with_code,
+			MemoryAccount {
+				code: vec![0x00],
+				..Default::default()
+			},
+		);
+		accounts.insert(
+			with_nonce,
+			MemoryAccount {
+				nonce: U256::one(),
+				..Default::default()
+			},
+		);
+		let backend = MemoryBackend::new(&vicinity, accounts);
+		let config = Config::istanbul();
+		let metadata = StackSubstateMetadata::new(1_000_000, &config);
+		let state = MemoryStackState::new(metadata, &backend);
+
+		assert!(!state.is_creatable(with_code));
+		assert!(!state.is_creatable(with_nonce));
+		assert!(state.is_creatable(fresh));
+	}
+}
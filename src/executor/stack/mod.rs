@@ -2,14 +2,21 @@
 //! A memory-based state is provided, but can replaced by a custom
 //! implementation, for exemple one interacting with a database.
 
+// `auto_impl` forwards the deprecated `StackState::inc_nonce` for `&mut` and
+// `Box`.
+#[allow(deprecated)]
 mod executor;
 mod memory;
 
 pub use self::executor::{
-	Accessed, PrecompileFailure, PrecompileFn, PrecompileHandle, PrecompileOutput, PrecompileSet,
-	StackExecutor, StackExitKind, StackState, StackSubstateMetadata,
+	run_code, storage_diff, AccessList, AccessListItem, Accessed, BaseCostPrecompileSet,
+	BlockContext, CallOutcome, PrecompileFailure, PrecompileFn, PrecompileHandle, PrecompileOutput,
+	PrecompileSet, StackExecutor, StackExitKind, StackState, StackSubstateMetadata, Transaction,
+	RUN_CODE_ADDRESS,
 };
 
-pub use self::memory::{MemoryStackAccount, MemoryStackState, MemoryStackSubstate};
+pub use self::memory::{
+	MemoryStackAccount, MemoryStackState, MemoryStackSubstate, StorageObserver,
+};
 
 pub use ethereum::Log;
@@ -28,3 +28,6 @@ macro_rules! event {
 
 pub mod backend;
 pub mod executor;
+
+#[cfg(test)]
+mod test_utils;
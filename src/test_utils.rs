@@ -0,0 +1,89 @@
+//! Fixtures shared by the unit tests of this crate.
+
+use crate::backend::{MemoryAccount, MemoryBackend, MemoryVicinity};
+use crate::executor::stack::{
+	MemoryStackState, PrecompileFn, PrecompileSet, StackExecutor, StackSubstateMetadata,
+};
+use crate::{Config, ExitReason};
+use alloc::collections::BTreeMap;
+use primitive_types::{H160, H256, U256};
+
+/// The executor built by `with_executor`.
+pub type Executor<'a> = ExecutorWith<'a, BTreeMap<H160, PrecompileFn>>;
+
+/// The executor built by `with_executor_and_precompiles`.
+pub type ExecutorWith<'a, P> =
+	StackExecutor<'a, 'a, MemoryStackState<'a, 'a, MemoryBackend<'a>>, P>;
+
+pub const CALLER: H160 = H160::repeat_byte(0x01);
+pub const CONTRACT: H160 = H160::repeat_byte(0x02);
+pub const GAS_LIMIT: u64 = 1_000_000;
+
+/// Block 1 with free gas, a known `PREVRANDAO` and two blob hashes.
+pub fn vicinity() -> MemoryVicinity {
+	MemoryVicinity {
+		gas_price: U256::zero(),
+		origin: CALLER,
+		chain_id: U256::one(),
+		block_hashes: Vec::new(),
+		block_number: U256::one(),
+		block_coinbase: H160::default(),
+		block_timestamp: U256::zero(),
+		block_difficulty: U256::from(0x0d),
+		block_randomness: Some(H256::repeat_byte(0x5a)),
+		block_gas_limit: U256::from(u64::MAX),
+		block_base_fee_per_gas: U256::zero(),
+		blob_base_fee: U256::from(7),
+		blob_hashes: vec![H256::repeat_byte(0xb0), H256::repeat_byte(0xb1)],
+	}
+}
+
+/// Run `f` against an executor whose state holds a funded caller and a
+/// contract with the given code.
+pub fn with_executor<R>(config: &Config, code: Vec<u8>, f: impl FnOnce(&mut Executor) -> R) -> R {
+	with_executor_and_gas_limit(config, code, GAS_LIMIT, f)
+}
+
+pub fn with_executor_and_gas_limit<R>(
+	config: &Config,
+	code: Vec<u8>,
+	gas_limit: u64,
+	f: impl FnOnce(&mut Executor) -> R,
+) -> R {
+	with_executor_and_precompiles(config, code, gas_limit, &BTreeMap::new(), f)
+}
+
+pub fn with_executor_and_precompiles<P: PrecompileSet, R>(
+	config: &Config,
+	code: Vec<u8>,
+	gas_limit: u64,
+	precompiles: &P,
+	f: impl FnOnce(&mut ExecutorWith<P>) -> R,
+) -> R {
+	let vicinity = vicinity();
+	let mut state = BTreeMap::new();
+	state.insert(
+		CALLER,
+		MemoryAccount {
+			balance: U256::from(10_000_000),
+			..Default::default()
+		},
+	);
+	state.insert(
+		CONTRACT,
+		MemoryAccount {
+			code,
+			..Default::default()
+		},
+	);
+	let backend = MemoryBackend::new(&vicinity, state);
+	let metadata = StackSubstateMetadata::new(gas_limit, config);
+	let state = MemoryStackState::new(metadata, &backend);
+	let mut executor = StackExecutor::new_with_precompiles(state, config, precompiles);
+	f(&mut executor)
+}
+
+/// Call `CONTRACT` from `CALLER` with `input` and the whole `GAS_LIMIT`.
+pub fn call(executor: &mut Executor, input: Vec<u8>) -> (ExitReason, Vec<u8>) {
+	executor.transact_call(CALLER, CONTRACT, U256::zero(), input, GAS_LIMIT, Vec::new())
+}
@@ -1,6 +1,7 @@
 //! Allows to listen to runtime events.
 
 use crate::Context;
+use alloc::vec::Vec;
 use evm_runtime::{CreateScheme, ExitReason, Transfer};
 use primitive_types::{H160, H256, U256};
 
@@ -69,6 +70,116 @@ pub enum Event<'a> {
 	},
 }
 
+/// Kind of a traced call frame.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum CallTraceKind {
+	Call,
+	StaticCall,
+	Create(CreateScheme),
+}
+
+/// A call frame and the frames it spawned.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CallTrace {
+	pub kind: CallTraceKind,
+	pub from: H160,
+	pub to: H160,
+	pub value: U256,
+	pub input: Vec<u8>,
+	pub gas: Option<u64>,
+	pub output: Vec<u8>,
+	/// Exit reason of the frame, `None` if it has not exited yet.
+	pub reason: Option<ExitReason>,
+	pub children: Vec<CallTrace>,
+}
+
+/// Listener building the call tree of a transaction out of `Call`, `Create`
+/// and `Exit` events.
+#[derive(Debug, Default, Clone)]
+pub struct CallTracer {
+	stack: Vec<CallTrace>,
+	root: Option<CallTrace>,
+}
+
+impl CallTracer {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Take the call tree of the last completed top-level frame.
+	pub fn take_call_trace(&mut self) -> Option<CallTrace> {
+		self.root.take()
+	}
+
+	fn enter(&mut self, trace: CallTrace) {
+		self.stack.push(trace);
+	}
+
+	fn exit(&mut self, reason: &ExitReason, return_value: &[u8]) {
+		// Exits of transactions failing before any frame is entered are ignored.
+		if let Some(mut trace) = self.stack.pop() {
+			trace.reason = Some(reason.clone());
+			trace.output = return_value.to_vec();
+
+			match self.stack.last_mut() {
+				Some(parent) => parent.children.push(trace),
+				None => self.root = Some(trace),
+			}
+		}
+	}
+}
+
+impl EventListener for CallTracer {
+	fn event(&mut self, event: Event) {
+		match event {
+			Event::Call {
+				input,
+				target_gas,
+				is_static,
+				context,
+				..
+			} => self.enter(CallTrace {
+				kind: if is_static {
+					CallTraceKind::StaticCall
+				} else {
+					CallTraceKind::Call
+				},
+				from: context.caller,
+				to: context.address,
+				value: context.apparent_value,
+				input: input.to_vec(),
+				gas: target_gas,
+				output: Vec::new(),
+				reason: None,
+				children: Vec::new(),
+			}),
+			Event::Create {
+				caller,
+				address,
+				scheme,
+				value,
+				init_code,
+				target_gas,
+			} => self.enter(CallTrace {
+				kind: CallTraceKind::Create(scheme),
+				from: caller,
+				to: address,
+				value,
+				input: init_code.to_vec(),
+				gas: target_gas,
+				output: Vec::new(),
+				reason: None,
+				children: Vec::new(),
+			}),
+			Event::Exit {
+				reason,
+				return_value,
+			} => self.exit(reason, return_value),
+			_ => (),
+		}
+	}
+}
+
 // Expose `listener::with` to the crate only.
 pub(crate) fn with<F: FnOnce(&mut (dyn EventListener + 'static))>(f: F) {
 	listener::with(f);